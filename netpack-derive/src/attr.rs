@@ -0,0 +1,96 @@
+use proc_macro2::Span;
+use syn::{Attribute, Error, Expr, ExprLit, Field, Ident, Lit, LitInt, Result, Variant};
+
+pub struct Container {
+    pub tag_type: Ident,
+}
+impl Container {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut tag_type = None;
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("netpack")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag_type") {
+                    let ty: Ident = meta.value()?.parse()?;
+                    match ty.to_string().as_str() {
+                        "u8" | "u16" | "u32" | "u64" => tag_type = Some(ty),
+                        _ => return Err(Error::new_spanned(ty, "tag_type must be one of u8, u16, u32 or u64")),
+                    }
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported netpack container attribute"))
+                }
+            })?;
+        }
+
+        Ok(Self {
+            tag_type: tag_type.unwrap_or_else(|| Ident::new("u8", Span::call_site())),
+        })
+    }
+
+    pub fn tag_max(&self) -> u64 {
+        match self.tag_type.to_string().as_str() {
+            "u8" => u8::MAX.into(),
+            "u16" => u16::MAX.into(),
+            "u32" => u32::MAX.into(),
+            _ => u64::MAX,
+        }
+    }
+}
+
+pub struct VariantAttrs {
+    pub tag: Option<LitInt>,
+}
+impl VariantAttrs {
+    pub fn from_variant(variant: &Variant) -> Result<Self> {
+        let mut tag = None;
+
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("netpack")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    tag = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported netpack variant attribute"))
+                }
+            })?;
+        }
+        // Fall back to an explicit `= N` discriminant so C-like enums keep their values on the wire
+        if tag.is_none() {
+            match &variant.discriminant {
+                Some((_, Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }))) => tag = Some(lit.clone()),
+                Some((_, discriminant)) => {
+                    return Err(Error::new_spanned(
+                        discriminant,
+                        "discriminant must be an integer literal to be used as a tag; set one with #[netpack(tag = N)]",
+                    ))
+                }
+                None => {}
+            }
+        }
+
+        Ok(Self { tag })
+    }
+}
+
+pub struct FieldAttrs {
+    pub flag: bool,
+}
+impl FieldAttrs {
+    pub fn from_field(field: &Field) -> Result<Self> {
+        let mut flag = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("netpack")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("flag") {
+                    flag = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported netpack field attribute"))
+                }
+            })?;
+        }
+
+        Ok(Self { flag })
+    }
+}
@@ -0,0 +1,31 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, DeriveInput, GenericParam, Lifetime, LifetimeParam, Result};
+
+use crate::{bound, unpack};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    // Buffer lifetime, which must outlive every lifetime the type borrows for
+    let de = Lifetime::new("'__de", Span::call_site());
+    let body = unpack::body(input, |ty| quote!(<#ty as ::netpack::UnpackBorrowed<#de>>::unpack_borrowed(buf)?))?;
+
+    let mut generics = bound::add_bounds(&input.generics, &bound::generic_types(&input.generics, bound::field_types(&input.data)), |ty| {
+        vec![parse_quote!(#ty: ::netpack::UnpackBorrowed<#de>)]
+    });
+    let mut de_param = LifetimeParam::new(de.clone());
+    de_param.bounds.extend(input.generics.lifetimes().map(|l| l.lifetime.clone()));
+    generics.params.insert(0, GenericParam::Lifetime(de_param));
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl #impl_generics ::netpack::UnpackBorrowed<#de> for #name #ty_generics #where_clause {
+            fn unpack_borrowed(buf: &mut &#de [u8]) -> ::core::result::Result<Self, ::netpack::PackError> {
+                let _ = &buf;
+                #body
+            }
+        }
+    })
+}
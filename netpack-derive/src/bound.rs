@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{Data, Field, Generics, Ident, Type, WherePredicate};
+
+use crate::attr::FieldAttrs;
+use crate::flags::option_inner;
+
+struct ParamFinder<'a> {
+    params: &'a HashSet<Ident>,
+    found: bool,
+}
+impl<'ast> Visit<'ast> for ParamFinder<'_> {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if let Some(seg) = path.segments.first() {
+            if path.leading_colon.is_none() && self.params.contains(&seg.ident) {
+                self.found = true;
+            }
+        }
+        visit::visit_path(self, path);
+    }
+}
+
+// Type each field is packed as: its own type, or the `Option`'s inner type for flag fields
+fn packed_type(field: &Field) -> &Type {
+    match FieldAttrs::from_field(field) {
+        Ok(attrs) if attrs.flag => option_inner(&field.ty).unwrap_or(&field.ty),
+        _ => &field.ty,
+    }
+}
+
+pub fn field_types(data: &Data) -> Vec<&Type> {
+    match data {
+        Data::Struct(data) => data.fields.iter().map(packed_type).collect(),
+        Data::Enum(data) => data.variants.iter().flat_map(|v| v.fields.iter().map(packed_type)).collect(),
+        Data::Union(data) => data.fields.named.iter().map(packed_type).collect(),
+    }
+}
+
+// Field types that mention a generic type parameter, deduplicated. Only these need bounds,
+// since concrete field types are checked by the compiler regardless.
+pub fn generic_types<'a>(generics: &Generics, types: impl IntoIterator<Item = &'a Type>) -> Vec<&'a Type> {
+    let params: HashSet<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+
+    if params.is_empty() {
+        return out;
+    }
+    for ty in types {
+        let mut finder = ParamFinder { params: &params, found: false };
+        finder.visit_type(ty);
+        if finder.found && seen.insert(ty.to_token_stream().to_string()) {
+            out.push(ty);
+        }
+    }
+
+    out
+}
+
+pub fn add_bounds(generics: &Generics, types: &[&Type], bound: impl Fn(&Type) -> Vec<WherePredicate>) -> Generics {
+    let mut generics = generics.clone();
+    let clause = generics.make_where_clause();
+    for ty in types {
+        clause.predicates.extend(bound(ty));
+    }
+    generics
+}
@@ -0,0 +1,92 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Fields, Ident, Result, Type};
+
+use crate::flags::Flags;
+
+// Destructuring pattern binding each field to `__fieldN`, along with the bound names in order
+pub fn pattern(fields: &Fields) -> (TokenStream, Vec<Ident>) {
+    let names: Vec<_> = (0..fields.len())
+        .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+        .collect();
+    let pat = match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|f| &f.ident);
+            quote!({ #(#idents: #names),* })
+        }
+        Fields::Unnamed(_) => quote!((#(#names),*)),
+        Fields::Unit => quote!(),
+    };
+
+    (pat, names)
+}
+
+// Expression building `path` from fields unpacked with `read` in declaration order. Flag fields
+// are preceded by their shared header and only read when their bit is set.
+pub fn construct(path: TokenStream, fields: &Fields, read: &impl Fn(&Type) -> TokenStream) -> Result<TokenStream> {
+    let flags = Flags::from_fields(fields)?;
+    let values = fields.iter().enumerate().map(|(i, f)| {
+        let value = match flags.as_ref().and_then(|flags| Some((flags, flags.bit(i)?))) {
+            Some((flags, bit)) => {
+                let mask = flags.mask(bit);
+                let inner = read(flags.fields[bit].1);
+                quote! {
+                    if __flags & #mask != 0 {
+                        ::core::option::Option::Some(#inner)
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+            }
+            None => read(&f.ty),
+        };
+        match &f.ident {
+            Some(ident) => quote!(#ident: #value),
+            None => value,
+        }
+    });
+
+    let value = match fields {
+        Fields::Named(_) => quote!(#path { #(#values),* }),
+        Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+        Fields::Unit => path,
+    };
+    Ok(match &flags {
+        Some(flags) => {
+            let ty = &flags.ty;
+            let read_flags = read(&parse_quote!(#ty));
+            let unused = flags.unused();
+            quote! {{
+                let __flags: #ty = #read_flags;
+                if __flags & #unused != 0 {
+                    return ::core::result::Result::Err(::netpack::PackError::InvalidValue("unknown presence flags set"));
+                }
+                #value
+            }}
+        }
+        None => value,
+    })
+}
+
+// Statements packing each bound field with `pack`, flag fields after their header and only when
+// present
+pub fn pack(fields: &Fields, names: &[Ident], pack: impl Fn(TokenStream) -> TokenStream) -> Result<TokenStream> {
+    let flags = Flags::from_fields(fields)?;
+    let header = flags.as_ref().map(|flags| {
+        let header = flags.header(names);
+        let pack_header = pack(quote!(&__flags));
+        quote!(#header #pack_header;)
+    });
+    let stmts = names.iter().enumerate().map(|(i, name)| match flags.as_ref().and_then(|flags| flags.bit(i)) {
+        Some(_) => {
+            let value = pack(quote!(__value));
+            quote!(if let ::core::option::Option::Some(__value) = #name { #value; })
+        }
+        None => {
+            let value = pack(quote!(#name));
+            quote!(#value;)
+        }
+    });
+
+    Ok(quote!(#header #(#stmts)*))
+}
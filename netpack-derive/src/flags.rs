@@ -0,0 +1,93 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Error, Fields, GenericArgument, Ident, LitInt, PathArguments, Result, Type};
+
+use crate::attr::FieldAttrs;
+
+// `Option` fields marked `#[netpack(flag)]`, whose presence bits are packed together in a header
+// ahead of the fields. Bit 0 belongs to the first flagged field.
+pub struct Flags<'a> {
+    pub ty: Ident,
+    // Field index and the `Option`'s inner type for each flagged field
+    pub fields: Vec<(usize, &'a Type)>,
+}
+impl<'a> Flags<'a> {
+    pub fn from_fields(fields: &'a Fields) -> Result<Option<Self>> {
+        let mut flagged = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            if FieldAttrs::from_field(field)?.flag {
+                match option_inner(&field.ty) {
+                    Some(inner) => flagged.push((i, inner)),
+                    None => return Err(Error::new_spanned(&field.ty, "flag fields must be an `Option`")),
+                }
+            }
+        }
+
+        let ty = match flagged.len() {
+            0 => return Ok(None),
+            1..=8 => "u8",
+            9..=16 => "u16",
+            17..=32 => "u32",
+            33..=64 => "u64",
+            _ => return Err(Error::new_spanned(fields, "at most 64 fields can be flags")),
+        };
+        Ok(Some(Self { ty: Ident::new(ty, Span::call_site()), fields: flagged }))
+    }
+
+    // Index of field `i` among the flagged fields
+    pub fn bit(&self, i: usize) -> Option<usize> {
+        self.fields.iter().position(|&(f, _)| f == i)
+    }
+
+    pub fn mask(&self, bit: usize) -> LitInt {
+        LitInt::new(&format!("{}{}", 1u64 << bit, self.ty), Span::call_site())
+    }
+
+    // Bits that don't belong to any field, which unpacking rejects
+    pub fn unused(&self) -> LitInt {
+        let used = u64::MAX >> (64 - self.fields.len());
+        LitInt::new(&format!("{}{}", !used & self.max(), self.ty), Span::call_site())
+    }
+
+    fn max(&self) -> u64 {
+        match self.ty.to_string().as_str() {
+            "u8" => u8::MAX.into(),
+            "u16" => u16::MAX.into(),
+            "u32" => u32::MAX.into(),
+            _ => u64::MAX,
+        }
+    }
+
+    // Statement declaring `__flags` from the presence of each bound field
+    pub fn header(&self, names: &[Ident]) -> TokenStream {
+        let ty = &self.ty;
+        let bits = self.fields.iter().enumerate().map(|(bit, &(i, _))| {
+            let name = &names[i];
+            let mask = self.mask(bit);
+            quote!(if ::core::option::Option::is_some(#name) { __flags |= #mask; })
+        });
+        quote! {
+            let mut __flags: #ty = 0;
+            #(#bits)*
+        }
+    }
+}
+
+// `T` for a field type spelled `Option<T>`
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
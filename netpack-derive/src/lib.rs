@@ -0,0 +1,47 @@
+extern crate proc_macro;
+
+mod attr;
+mod borrowed;
+mod bound;
+mod fields;
+mod flags;
+mod pack;
+mod size;
+mod tag;
+mod unpack;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+#[proc_macro_derive(Packable, attributes(netpack))]
+pub fn derive_packable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    pack::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Unpackable, attributes(netpack))]
+pub fn derive_unpackable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    unpack::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(PackedSize, attributes(netpack))]
+pub fn derive_packed_size(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    size::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(UnpackBorrowed, attributes(netpack))]
+pub fn derive_unpack_borrowed(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    borrowed::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
@@ -0,0 +1,66 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, Result};
+
+use crate::attr::Container;
+use crate::{bound, fields, tag};
+
+fn pack_field(value: TokenStream) -> TokenStream {
+    quote!(::netpack::Packable::pack_into(#value, stream)?)
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pat, names) = fields::pattern(&data.fields);
+            let stmts = fields::pack(&data.fields, &names, pack_field)?;
+            quote! {
+                let Self #pat = self;
+                #stmts
+            }
+        }
+        Data::Enum(data) => {
+            let tag_type = &container.tag_type;
+            let tags = tag::tags(data, &container)?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let (pat, names) = fields::pattern(&variant.fields);
+                let stmts = fields::pack(&variant.fields, &names, pack_field)?;
+                Ok(quote! {
+                    Self::#ident #pat => {
+                        ::netpack::Packable::pack_into(&(#tag as #tag_type), stream)?;
+                        #stmts
+                    }
+                })
+            }).collect::<Result<Vec<_>>>()?;
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "Packable cannot be derived for unions")),
+    };
+
+    let generics = bound::add_bounds(&input.generics, &bound::generic_types(&input.generics, bound::field_types(&input.data)), |ty| {
+        vec![
+            parse_quote!(#ty: ::netpack::Packable),
+            parse_quote!(::netpack::PackError: ::core::convert::From<<#ty as ::netpack::Packable>::Error>),
+        ]
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl #impl_generics ::netpack::Packable for #name #ty_generics #where_clause {
+            type Error = ::netpack::PackError;
+
+            fn pack_into(&self, stream: &mut impl ::netpack::io::Write) -> ::core::result::Result<(), Self::Error> {
+                let _ = &stream;
+                #body
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
@@ -0,0 +1,88 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, Fields, Ident, Result};
+
+use crate::attr::Container;
+use crate::flags::Flags;
+use crate::{bound, fields};
+
+// `Option<usize>` expression for the combined size of `fields`. Flag fields make it variable.
+fn fields_size(fields: &Fields) -> Result<TokenStream> {
+    if Flags::from_fields(fields)?.is_some() {
+        return Ok(quote!(::core::option::Option::None));
+    }
+    Ok(fields.iter().fold(quote!(::core::option::Option::Some(0)), |acc, f| {
+        let ty = &f.ty;
+        quote!(::netpack::__private::size_add(#acc, <#ty as ::netpack::PackedSize>::SIZE))
+    }))
+}
+
+// Expression summing the packed length of each bound field, saturating like the built-in impls
+fn fields_len(fields: &Fields, names: &[Ident]) -> Result<TokenStream> {
+    let flags = Flags::from_fields(fields)?;
+    let header = flags.as_ref().map(|flags| {
+        let ty = &flags.ty;
+        quote!(.saturating_add(::core::mem::size_of::<#ty>()))
+    });
+    let lens = names.iter().enumerate().map(|(i, name)| match flags.as_ref().and_then(|flags| flags.bit(i)) {
+        Some(_) => quote!(::core::option::Option::map_or(::core::option::Option::as_ref(#name), 0, |v| ::netpack::PackedSize::packed_len(v))),
+        None => quote!(::netpack::PackedSize::packed_len(#name)),
+    });
+    Ok(quote!(0usize #header #(.saturating_add(#lens))*))
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let (size, len) = match &input.data {
+        Data::Struct(data) => {
+            let (pat, names) = fields::pattern(&data.fields);
+            let len = fields_len(&data.fields, &names)?;
+            let len = quote! {
+                let Self #pat = self;
+                #len
+            };
+            (fields_size(&data.fields)?, len)
+        }
+        Data::Enum(data) => {
+            let tag_type = &container.tag_type;
+            // Only fixed if every variant packs to the same size
+            let variants = data.variants.iter().map(|v| fields_size(&v.fields)).collect::<Result<Vec<_>>>()?;
+            let variants = variants.into_iter().reduce(|acc, size| quote!(::netpack::__private::size_same(#acc, #size)));
+            let size = match variants {
+                Some(variants) => quote!(::netpack::__private::size_add(<#tag_type as ::netpack::PackedSize>::SIZE, #variants)),
+                None => quote!(::core::option::Option::None),
+            };
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let (pat, names) = fields::pattern(&variant.fields);
+                let len = fields_len(&variant.fields, &names)?;
+                Ok(quote! {
+                    Self::#ident #pat => ::core::mem::size_of::<#tag_type>().saturating_add(#len),
+                })
+            }).collect::<Result<Vec<_>>>()?;
+            let len = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            (size, len)
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "PackedSize cannot be derived for unions")),
+    };
+
+    let generics = bound::add_bounds(&input.generics, &bound::generic_types(&input.generics, bound::field_types(&input.data)), |ty| {
+        vec![parse_quote!(#ty: ::netpack::PackedSize)]
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl #impl_generics ::netpack::PackedSize for #name #ty_generics #where_clause {
+            const SIZE: ::core::option::Option<usize> = #size;
+
+            fn packed_len(&self) -> usize {
+                #len
+            }
+        }
+    })
+}
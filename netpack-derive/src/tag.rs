@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+
+use proc_macro2::Span;
+use syn::{DataEnum, Error, LitInt, Result};
+
+use crate::attr::{Container, VariantAttrs};
+
+// Wire tag of every variant: explicit `#[netpack(tag = N)]` or `= N` values, otherwise one more
+// than the previous variant's tag, starting at 0.
+pub fn tags(data: &DataEnum, container: &Container) -> Result<Vec<LitInt>> {
+    let mut seen = HashSet::new();
+    let mut next = Some(0u64);
+    let mut out = Vec::with_capacity(data.variants.len());
+
+    for variant in &data.variants {
+        let attrs = VariantAttrs::from_variant(variant)?;
+        let (value, span) = match &attrs.tag {
+            Some(lit) => (lit.base10_parse::<u64>()?, lit.span()),
+            None => match next {
+                Some(value) => (value, Span::call_site()),
+                None => return Err(Error::new_spanned(variant, "implicit tag overflows u64")),
+            },
+        };
+        if value > container.tag_max() {
+            return Err(Error::new(span, format!("tag {} does not fit in {}", value, container.tag_type)));
+        }
+        if !seen.insert(value) {
+            return Err(Error::new_spanned(variant, format!("duplicate tag {}", value)));
+        }
+
+        next = value.checked_add(1);
+        out.push(LitInt::new(&value.to_string(), span));
+    }
+
+    Ok(out)
+}
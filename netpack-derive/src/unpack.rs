@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, Result, Type};
+
+use crate::attr::Container;
+use crate::{bound, fields, tag};
+
+// Body that unpacks `Self`, reading each field (and enum tag) with `read`
+pub fn body(input: &DeriveInput, read: impl Fn(&Type) -> TokenStream) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    match &input.data {
+        Data::Struct(data) => {
+            let construct = fields::construct(quote!(Self), &data.fields, &read)?;
+            Ok(quote!(::core::result::Result::Ok(#construct)))
+        }
+        Data::Enum(data) => {
+            let tag_type = &container.tag_type;
+            let tag_type: Type = parse_quote!(#tag_type);
+            let read_tag = read(&tag_type);
+            let tags = tag::tags(data, &container)?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let construct = fields::construct(quote!(Self::#ident), &variant.fields, &read)?;
+                Ok(quote! {
+                    #tag => ::core::result::Result::Ok(#construct),
+                })
+            }).collect::<Result<Vec<_>>>()?;
+            Ok(quote! {
+                match #read_tag {
+                    #(#arms)*
+                    tag => ::core::result::Result::Err(::netpack::PackError::UnknownTag(::core::convert::From::from(tag))),
+                }
+            })
+        }
+        Data::Union(_) => Err(Error::new_spanned(input, "unpacking cannot be derived for unions")),
+    }
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let body = body(input, |ty| quote!(<#ty as ::netpack::Unpackable>::unpack_from(rdr)?))?;
+
+    let generics = bound::add_bounds(&input.generics, &bound::generic_types(&input.generics, bound::field_types(&input.data)), |ty| {
+        vec![
+            parse_quote!(#ty: ::netpack::Unpackable),
+            parse_quote!(::netpack::PackError: ::core::convert::From<<#ty as ::netpack::Unpackable>::Error>),
+        ]
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl #impl_generics ::netpack::Unpackable for #name #ty_generics #where_clause {
+            type Error = ::netpack::PackError;
+
+            fn unpack_from(rdr: &mut impl ::netpack::io::Read) -> ::core::result::Result<Self, Self::Error> {
+                let _ = &rdr;
+                #body
+            }
+        }
+    })
+}
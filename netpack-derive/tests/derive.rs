@@ -0,0 +1,333 @@
+use netpack::{unpack, unpack_borrowed, AsyncReadPackExt, LenPrefixed, NulTerminated, PackError, PackedSize, Packable, UnpackBorrowed, Unpackable, Usize, VarU64};
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+struct Named {
+    num: i32,
+    arr: [u16; 2],
+    b: bool,
+}
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+struct Tuple(u8, Named);
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+struct Unit;
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+struct Generic<T, const S: usize> {
+    head: u8,
+    body: [T; S],
+}
+
+#[test]
+fn test_pack_named() {
+    let v = Named { num: 20, arr: [10, 1000], b: true };
+
+    assert_eq!(v.pack().unwrap(), vec![0, 0, 0, 20, 0, 10, 3, 232, 1]);
+}
+
+#[test]
+fn test_unpack_named() {
+    let i1 = [0, 0, 0, 20, 0, 10, 3, 232];
+    let i2 = [0, 0, 0, 20, 0, 10, 3, 232, 1, 0xff];
+
+    let v1: Result<(Named, _), PackError> = unpack(&i1);
+    let (v2, b2): (Named, _) = unpack(&i2).unwrap();
+
+    assert_eq!(v1, Err(PackError::SizeError));
+    assert_eq!(v2, Named { num: 20, arr: [10, 1000], b: true });
+    assert_eq!(b2, &[0xff]);
+}
+
+#[test]
+fn test_roundtrip_tuple() {
+    let v = Tuple(5, Named { num: -5, arr: [0, 0xffff], b: false });
+
+    let i = v.pack().unwrap();
+    let (t, tb): (Tuple, _) = unpack(&i).unwrap();
+
+    assert_eq!(i.len(), 10);
+    assert_eq!(t, v);
+    assert_eq!(tb, &[]);
+}
+
+#[test]
+fn test_roundtrip_unit() {
+    let i = Unit.pack().unwrap();
+    let (t, tb): (Unit, _) = unpack(&[0xff]).unwrap();
+
+    assert_eq!(i, vec![]);
+    assert_eq!(t, Unit);
+    assert_eq!(tb, &[0xff]);
+}
+
+#[test]
+fn test_roundtrip_generic() {
+    let v = Generic { head: 1, body: [0x0102u16, 0x0304, 0x0506] };
+
+    let i = v.pack().unwrap();
+    let (t, tb): (Generic<u16, 3>, _) = unpack(&i).unwrap();
+
+    assert_eq!(i, vec![1, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(t, v);
+    assert_eq!(tb, &[]);
+}
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+enum Message {
+    Ping,
+    Move { x: i16, y: i16 },
+    #[netpack(tag = 10)]
+    Chat(u8, bool),
+    Leave,
+}
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+#[netpack(tag_type = u16)]
+enum Wide<T> {
+    #[netpack(tag = 0x100)]
+    Empty,
+    Value(T),
+}
+
+#[test]
+fn test_pack_enum() {
+    assert_eq!(Message::Ping.pack().unwrap(), vec![0]);
+    assert_eq!(Message::Move { x: 1, y: -1 }.pack().unwrap(), vec![1, 0, 1, 0xff, 0xff]);
+    assert_eq!(Message::Chat(7, true).pack().unwrap(), vec![10, 7, 1]);
+    assert_eq!(Message::Leave.pack().unwrap(), vec![11]);
+    assert_eq!(Wide::<u8>::Empty.pack().unwrap(), vec![1, 0]);
+    assert_eq!(Wide::Value(5u8).pack().unwrap(), vec![1, 1, 5]);
+}
+
+#[test]
+fn test_unpack_enum() {
+    let i1 = [];
+    let i2 = [2];
+    let i3 = [1, 0, 1];
+    let i4 = [10, 7, 1, 0xff];
+    let i5 = [1, 2];
+
+    let v1: Result<(Message, _), PackError> = unpack(&i1);
+    let v2: Result<(Message, _), PackError> = unpack(&i2);
+    let v3: Result<(Message, _), PackError> = unpack(&i3);
+    let (v4, b4): (Message, _) = unpack(&i4).unwrap();
+    let v5: Result<(Wide<u8>, _), PackError> = unpack(&i5);
+
+    assert_eq!(v1, Err(PackError::SizeError));
+    assert_eq!(v2, Err(PackError::UnknownTag(2)));
+    assert_eq!(v3, Err(PackError::SizeError));
+    assert_eq!(v4, Message::Chat(7, true));
+    assert_eq!(b4, &[0xff]);
+    assert_eq!(v5, Err(PackError::UnknownTag(0x102)));
+}
+
+#[test]
+fn test_roundtrip_enum() {
+    let v1 = Message::Move { x: -300, y: 300 };
+    let v2 = Wide::Value(0x1234u16);
+
+    let i1 = v1.pack().unwrap();
+    let i2 = v2.pack().unwrap();
+
+    let (t1, t1b): (Message, _) = unpack(&i1).unwrap();
+    let (t2, t2b): (Wide<u16>, _) = unpack(&i2).unwrap();
+
+    assert_eq!(t1, v1);
+    assert_eq!(t1b, &[]);
+    assert_eq!(t2, v2);
+    assert_eq!(t2b, &[]);
+}
+
+#[derive(Debug, PartialEq, Packable, Unpackable)]
+enum Discriminants {
+    A = 3,
+    B,
+}
+
+#[test]
+fn test_roundtrip_discriminants() {
+    assert_eq!(Discriminants::A.pack().unwrap(), vec![3]);
+    assert_eq!(Discriminants::B.pack().unwrap(), vec![4]);
+
+    let (t, tb): (Discriminants, _) = unpack(&[4]).unwrap();
+
+    assert_eq!(t, Discriminants::B);
+    assert_eq!(tb, &[]);
+}
+
+#[derive(Debug, PartialEq, UnpackBorrowed)]
+struct Borrowed<'a> {
+    id: u16,
+    name: LenPrefixed<u8, &'a str>,
+    key: &'a [u8; 4],
+    body: NulTerminated<&'a [u8]>,
+}
+
+#[derive(Debug, PartialEq, UnpackBorrowed)]
+enum BorrowedMessage<'a, T> {
+    Empty,
+    Named(Borrowed<'a>),
+    Other(T),
+}
+
+#[test]
+fn test_unpack_borrowed_struct() {
+    let i = [0, 7, 2, b'h', b'i', 1, 2, 3, 4, 5, 6, 0, 0xff];
+
+    let (v, b): (Borrowed, _) = unpack_borrowed(&i).unwrap();
+
+    assert_eq!(v.id, 7);
+    assert_eq!(*v.name, "hi");
+    assert_eq!(v.key, &[1, 2, 3, 4]);
+    assert_eq!(v.body.0, &[5, 6]);
+    assert_eq!(v.key.as_ptr(), i[5..].as_ptr());
+    assert_eq!(b, &[0xff]);
+}
+
+#[test]
+fn test_unpack_borrowed_enum() {
+    let i1 = [1, 0, 7, 0, 1, 2, 3, 4, 0];
+    let i2 = [2, 0x12, 0x34];
+    let i3 = [3];
+
+    let (v1, b1): (BorrowedMessage<u16>, _) = unpack_borrowed(&i1).unwrap();
+    let (v2, b2): (BorrowedMessage<u16>, _) = unpack_borrowed(&i2).unwrap();
+    let v3: Result<(BorrowedMessage<u16>, _), PackError> = unpack_borrowed(&i3);
+
+    assert_eq!(
+        v1,
+        BorrowedMessage::Named(Borrowed { id: 7, name: LenPrefixed::new(""), key: &[1, 2, 3, 4], body: NulTerminated(&[]) })
+    );
+    assert_eq!(b1, &[]);
+    assert_eq!(v2, BorrowedMessage::Other(0x1234));
+    assert_eq!(b2, &[]);
+    assert_eq!(v3, Err(PackError::UnknownTag(3)));
+}
+
+#[tokio::test]
+async fn test_unpack_async() {
+    let v = Tuple(3, Named { num: -1, arr: [1, 2], b: true }).pack().unwrap();
+    let w = Generic { head: 1, body: [Wide::Value(2u8), Wide::Empty] }.pack().unwrap();
+    let i = [&v[..], &w[..], &[10, 7, 1, 4][..]].concat();
+
+    let mut rdr = &i[..];
+    let v1: Tuple = rdr.unpack().await.unwrap();
+    let v2: Generic<Wide<u8>, 2> = rdr.unpack().await.unwrap();
+    let v3: Message = rdr.unpack().await.unwrap();
+    let v4: Result<Message, PackError> = rdr.unpack().await;
+
+    assert_eq!(v1, Tuple(3, Named { num: -1, arr: [1, 2], b: true }));
+    assert_eq!(v2, Generic { head: 1, body: [Wide::Value(2), Wide::Empty] });
+    assert_eq!(v3, Message::Chat(7, true));
+    assert_eq!(v4, Err(PackError::UnknownTag(4)));
+}
+
+#[derive(Packable, PackedSize)]
+struct Variable {
+    id: u16,
+    name: LenPrefixed<VarU64, String>,
+}
+
+const _: () = assert!(matches!(Tuple::SIZE, Some(n) if n <= 1200));
+
+#[test]
+fn test_packed_size() {
+    let v1 = Tuple(3, Named { num: -1, arr: [1, 2], b: true });
+    let v2 = Variable { id: 1, name: String::from("abc").into() };
+    let v3 = Generic { head: 0, body: [Wide::Value(1u32), Wide::Empty] };
+
+    assert_eq!(Named::SIZE, Some(9));
+    assert_eq!(Tuple::SIZE, Some(10));
+    assert_eq!(Unit::SIZE, Some(0));
+    assert_eq!(<Generic<u16, 3>>::SIZE, Some(7));
+    assert_eq!(Message::SIZE, None);
+    assert_eq!(<Wide<[u8; 0]>>::SIZE, Some(2));
+    assert_eq!(Variable::SIZE, None);
+
+    assert_eq!(v1.packed_len(), 10);
+    assert_eq!(v2.packed_len(), v2.pack().unwrap().len());
+    assert_eq!(v3.packed_len(), v3.pack().unwrap().len());
+    assert_eq!(Message::Move { x: 1, y: 2 }.packed_len(), 5);
+    assert_eq!(Message::Leave.packed_len(), 1);
+    assert_eq!(Generic { head: 0, body: [Wide::Value(Usize::<u8>::new(256))] }.packed_len(), usize::MAX);
+}
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+struct Update<T> {
+    id: u16,
+    #[netpack(flag)]
+    pos: Option<[i16; 2]>,
+    plain: Option<u8>,
+    #[netpack(flag)]
+    value: Option<T>,
+}
+
+#[derive(Debug, PartialEq, Packable, PackedSize, Unpackable)]
+enum Flagged {
+    Empty,
+    Some(#[netpack(flag)] Option<u8>, #[netpack(flag)] Option<u16>),
+}
+
+#[derive(Debug, PartialEq, UnpackBorrowed)]
+struct BorrowedFlags<'a> {
+    #[netpack(flag)]
+    name: Option<LenPrefixed<u8, &'a str>>,
+    #[netpack(flag)]
+    key: Option<&'a [u8; 2]>,
+}
+
+#[test]
+fn test_pack_flags() {
+    let v1 = Update { id: 1, pos: Some([2, -1]), plain: None, value: Some(9u8) };
+    let v2 = Update::<u8> { id: 1, pos: None, plain: Some(3), value: None };
+
+    assert_eq!(v1.pack().unwrap(), vec![0b11, 0, 1, 0, 2, 0xff, 0xff, 0, 9]);
+    assert_eq!(v2.pack().unwrap(), vec![0, 0, 1, 1, 3]);
+    assert_eq!(Flagged::Some(None, Some(5)).pack().unwrap(), vec![1, 0b10, 0, 5]);
+    assert_eq!(v1.packed_len(), 9);
+    assert_eq!(v2.packed_len(), 5);
+    assert_eq!(<Update<u8>>::SIZE, None);
+    assert_eq!(Flagged::Some(Some(1), Some(5)).packed_len(), 5);
+}
+
+#[test]
+fn test_unpack_flags() {
+    let i1 = [0b10, 0, 1, 0, 7];
+    let i2 = [0b100, 0, 1, 0];
+    let i3 = [1, 0b11, 4, 0, 5];
+    let i4 = [1, 0b100];
+
+    let v1: Result<(Update<u8>, _), PackError> = unpack(&i1);
+    let v2: Result<(Update<u8>, _), PackError> = unpack(&i2);
+    let v3: Result<(Flagged, _), PackError> = unpack(&i3);
+    let v4: Result<(Flagged, _), PackError> = unpack(&i4);
+    let (v5, _): (BorrowedFlags, _) = unpack_borrowed(&[0b10, 1, 2]).unwrap();
+
+    assert_eq!(v1, Ok((Update { id: 1, pos: None, plain: None, value: Some(7) }, &[][..])));
+    assert_eq!(v2, Err(PackError::InvalidValue("unknown presence flags set")));
+    assert_eq!(v3, Ok((Flagged::Some(Some(4), Some(5)), &[][..])));
+    assert_eq!(v4, Err(PackError::InvalidValue("unknown presence flags set")));
+    assert_eq!(v5, BorrowedFlags { name: None, key: Some(&[1, 2]) });
+}
+
+#[derive(Debug, PartialEq, Packable, Unpackable)]
+struct List {
+    value: u8,
+    next: Option<Box<List>>,
+}
+
+#[test]
+fn test_roundtrip_recursive() {
+    let v = List { value: 1, next: Some(Box::new(List { value: 2, next: None })) };
+
+    let i = v.pack().unwrap();
+    let (t, tb): (List, _) = unpack(&i).unwrap();
+    let deep: Vec<u8> = [1, 1].repeat(1000);
+    let e: Result<(List, _), PackError> = unpack(&deep);
+
+    assert_eq!(i, vec![1, 1, 2, 0]);
+    assert_eq!(t, v);
+    assert_eq!(tb, &[]);
+    assert_eq!(e, Err(PackError::RecursionLimit { max: netpack::DEFAULT_RECURSION_LIMIT }));
+}
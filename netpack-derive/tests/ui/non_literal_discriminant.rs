@@ -0,0 +1,16 @@
+use netpack::Packable;
+
+#[derive(Packable)]
+enum Shifted {
+    A = 1 << 3,
+    B,
+}
+
+#[derive(Packable)]
+#[repr(i8)]
+enum Negative {
+    A = -1,
+    B,
+}
+
+fn main() {}
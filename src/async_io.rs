@@ -0,0 +1,248 @@
+use core::future::Future;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{try_unpack, PackError, Packable, Status, Unpackable};
+
+/// Packs a value onto an async stream. Implemented for every `Packable` by packing into a buffer
+/// and writing it out in one go.
+pub trait AsyncPackable: Packable {
+    fn pack_into_async<W: AsyncWrite + Unpin + Send + ?Sized>(&self, stream: &mut W) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+impl<T: Packable + ?Sized> AsyncPackable for T where T::Error: Send {
+    fn pack_into_async<W: AsyncWrite + Unpin + Send + ?Sized>(&self, stream: &mut W) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        let buf = self.pack();
+        async move {
+            stream.write_all(&buf?).await.map_err(PackError::from)?;
+            Ok(())
+        }
+    }
+}
+
+/// Unpacks a value from an async stream, reading only the bytes that belong to it. Implemented
+/// for every `Unpackable` by retrying the synchronous impl with `try_unpack` as bytes arrive, so
+/// both paths follow the same wire format and limits.
+pub trait AsyncUnpackable: Sized {
+    type Error: From<PackError>;
+
+    fn unpack_from_async<R: AsyncRead + Unpin + Send + ?Sized>(rdr: &mut R) -> impl Future<Output = Result<Self, Self::Error>> + Send;
+}
+impl<T: Unpackable> AsyncUnpackable for T where T::Error: Send {
+    type Error = T::Error;
+
+    async fn unpack_from_async<R: AsyncRead + Unpin + Send + ?Sized>(rdr: &mut R) -> Result<Self, Self::Error> {
+        let mut buf = Vec::new();
+        loop {
+            // Never read past the reported shortfall, which the value is sure to need
+            let needed = match try_unpack::<T>(&buf)? {
+                Status::Complete((v, _)) => return Ok(v),
+                Status::Incomplete { needed } => needed.unwrap_or(1),
+            };
+            let start = buf.len();
+            buf.resize(start + needed, 0);
+            rdr.read_exact(&mut buf[start..]).await.map_err(PackError::from)?;
+        }
+    }
+}
+
+pub trait AsyncWritePackExt: AsyncWrite + Unpin + Send {
+    fn pack<T: AsyncPackable + ?Sized>(&mut self, pack: &T) -> impl Future<Output = Result<(), T::Error>> + Send;
+}
+impl<W: AsyncWrite + Unpin + Send + ?Sized> AsyncWritePackExt for W {
+    fn pack<T: AsyncPackable + ?Sized>(&mut self, pack: &T) -> impl Future<Output = Result<(), T::Error>> + Send {
+        pack.pack_into_async(self)
+    }
+}
+
+pub trait AsyncReadPackExt: AsyncRead + Unpin + Send {
+    fn unpack<T: AsyncUnpackable>(&mut self) -> impl Future<Output = Result<T, T::Error>> + Send;
+}
+impl<R: AsyncRead + Unpin + Send + ?Sized> AsyncReadPackExt for R {
+    fn unpack<T: AsyncUnpackable>(&mut self) -> impl Future<Output = Result<T, T::Error>> + Send {
+        T::unpack_from_async(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use core::num::{NonZeroU16, Wrapping};
+    use core::time::Duration;
+    use std::borrow::Cow;
+    use std::collections::{BTreeMap, HashSet, VecDeque};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_roundtrip_async_primitives() {
+        let mut v = Vec::new();
+        AsyncWritePackExt::pack(&mut v, &0x0123u16).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &Le(-2i32)).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &[1.5f64, -0.25]).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &true).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &Some(7u8)).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &(1u8, VarU64(2))).await.unwrap();
+        v.push(0xff);
+
+        let mut rdr = &v[..];
+        let t1: u16 = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t2: Le<i32> = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t3: [f64; 2] = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t4: bool = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t5: Option<u8> = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t6: (u8, VarU64) = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+
+        assert_eq!(v.len(), 28);
+        assert_eq!(t1, 0x0123);
+        assert_eq!(t2, Le(-2));
+        assert_eq!(t3, [1.5, -0.25]);
+        assert!(t4);
+        assert_eq!(t5, Some(7));
+        assert_eq!(t6, (1, VarU64(2)));
+        assert_eq!(rdr, &[0xff]);
+    }
+
+    #[tokio::test]
+    async fn test_unpack_async_errors() {
+        let v1: Result<u32, PackError> = AsyncReadPackExt::unpack(&mut &[0u8, 1, 2][..]).await;
+        let v2: Result<VarU64, PackError> = AsyncReadPackExt::unpack(&mut &[0x80u8, 0x00][..]).await;
+        let v3: Result<LenPrefixed<u8, String>, PackError> = AsyncReadPackExt::unpack(&mut &[2u8, 0xc3, 0x28][..]).await;
+        let v4: Result<LenPrefixed<u8, Vec<u16>>, PackError> = AsyncReadPackExt::unpack(&mut &[2u8, 0, 1, 0][..]).await;
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Err(PackError::InvalidVarInt));
+        assert_eq!(v3, Err(PackError::InvalidValue("string is not valid utf-8")));
+        assert_eq!(v4, Err(PackError::SizeError));
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_async_stream() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        let msg: LenPrefixed<QuicVarInt, String> = String::from("hello").into();
+        AsyncWritePackExt::pack(&mut client, &msg).await.unwrap();
+        AsyncWritePackExt::pack(&mut client, &VarI64(-300)).await.unwrap();
+        AsyncWritePackExt::pack(&mut client, &QuicVarInt(15293)).await.unwrap();
+
+        let t1: LenPrefixed<QuicVarInt, String> = AsyncReadPackExt::unpack(&mut server).await.unwrap();
+        let t2: VarI64 = AsyncReadPackExt::unpack(&mut server).await.unwrap();
+        let t3: QuicVarInt = AsyncReadPackExt::unpack(&mut server).await.unwrap();
+
+        assert_eq!(t1, msg);
+        assert_eq!(t2, VarI64(-300));
+        assert_eq!(t3, QuicVarInt(15293));
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_async_types() {
+        type Numbers = (NonZeroU16, Wrapping<u8>, Usize<u16>, Isize<i8>, Fixed<i16, 8>, Quantized<0, 10, 12>, Le<Fixed<i32, 16>>, Be<[u16; 2]>);
+        type Net = (IpAddr, SocketAddr, SocketAddrV6, MacAddr);
+        type Text = (NulTerminated<String>, FixedWidth<String, 8>, SecsNanos<Duration>, Millis<Duration>, Ntp64<Duration>);
+        type Nested = (LenPrefixed<u8, BTreeMap<u8, u16>>, LenPrefixed<VarU64, VecDeque<u8>>, LenPrefixed<u8, Sorted<HashSet<u8>>>, Box<u8>, Arc<Option<Box<u16>>>, Cow<'static, u32>);
+
+        let v1: Numbers = (
+            NonZeroU16::new(7).unwrap(),
+            Wrapping(3),
+            Usize::new(300),
+            Isize::new(-5),
+            Fixed::from_raw(384),
+            Quantized::new(2.5).unwrap(),
+            Le(Fixed::from_raw(-65536)),
+            Be([1, 2]),
+        );
+        let v2: Net = (
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 8080),
+            SocketAddrV6::new(Ipv6Addr::LOCALHOST, 53, 1, 2),
+            MacAddr::BROADCAST,
+        );
+        let d = Duration::new(2, 500_000_000);
+        let v3: Text = (NulTerminated(String::from("netpack")), FixedWidth(String::from("abc")), SecsNanos(d), Millis(d), Ntp64(d));
+        let v4: Nested = (
+            BTreeMap::from([(1, 2), (3, 4)]).into(),
+            VecDeque::from(vec![5, 6]).into(),
+            Sorted(HashSet::from([7, 8])).into(),
+            Box::new(9),
+            Arc::new(Some(Box::new(10))),
+            Cow::Owned(11),
+        );
+
+        let mut v = Vec::new();
+        AsyncWritePackExt::pack(&mut v, &v1).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &v2).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &v3).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &v4).await.unwrap();
+        AsyncWritePackExt::pack(&mut v, &Rc::new(12u8)).await.unwrap();
+        v.push(0xff);
+
+        let mut rdr = &v[..];
+        let t1: Numbers = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t2: Net = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t3: Text = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t4: Nested = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+        let t5: Rc<u8> = AsyncReadPackExt::unpack(&mut rdr).await.unwrap();
+
+        assert_eq!(t1, v1);
+        assert_eq!(t2, v2);
+        assert_eq!(t3, v3);
+        assert_eq!(t4, v4);
+        assert_eq!(*t5, 12);
+        assert_eq!(rdr, &[0xff]);
+    }
+
+    #[tokio::test]
+    async fn test_unpack_async_type_errors() {
+        let long = vec![b'a'; NulTerminated::<String>::MAX_LEN + 1];
+
+        let v1: Result<NonZeroU16, PackError> = AsyncReadPackExt::unpack(&mut &[0u8, 0][..]).await;
+        let v2: Result<Quantized<0, 10, 12>, PackError> = AsyncReadPackExt::unpack(&mut &[0x10u8, 0][..]).await;
+        let v3: Result<IpAddr, PackError> = AsyncReadPackExt::unpack(&mut &[5u8, 0, 0, 0, 0][..]).await;
+        let v4: Result<LenPrefixed<u8, BTreeMap<u8, u8>>, PackError> = AsyncReadPackExt::unpack(&mut &[2u8, 1, 2, 1, 3][..]).await;
+        let v5: Result<NulTerminated<Vec<u8>>, PackError> = AsyncReadPackExt::unpack(&mut &long[..]).await;
+        let v6: Result<Usize<u8>, PackError> = AsyncReadPackExt::unpack(&mut &[][..]).await;
+
+        assert_eq!(v1, Err(PackError::InvalidValue("zero for a non-zero integer")));
+        assert_eq!(v2, Err(PackError::InvalidValue("value is out of range")));
+        assert_eq!(v3, Err(PackError::UnknownTag(5)));
+        assert_eq!(v4, Err(PackError::InvalidValue("duplicate map key")));
+        assert_eq!(v5, Err(PackError::LengthLimit { len: long.len() as u64, max: long.len() as u64 - 1 }));
+        assert_eq!(v6, Err(PackError::SizeError));
+    }
+
+    #[derive(Debug)]
+    struct List(Option<Box<List>>);
+    impl List {
+        fn depth(&self) -> usize {
+            self.0.as_ref().map_or(0, |l| 1 + l.depth())
+        }
+    }
+    impl Unpackable for List {
+        type Error = PackError;
+
+        fn unpack_from(rdr: &mut impl io::Read) -> Result<Self, Self::Error> {
+            Ok(List(unpack_from(rdr)?))
+        }
+    }
+
+    // A list nested `depth` boxes deep
+    fn list_bytes(depth: usize) -> Vec<u8> {
+        let mut v = vec![1; depth];
+        v.push(0);
+        v
+    }
+
+    #[tokio::test]
+    async fn test_async_recursion_limit() {
+        let v1: Result<List, PackError> = AsyncReadPackExt::unpack(&mut &list_bytes(DEFAULT_RECURSION_LIMIT)[..]).await;
+        let v2: Result<List, PackError> = AsyncReadPackExt::unpack(&mut &list_bytes(DEFAULT_RECURSION_LIMIT + 1)[..]).await;
+        let v3: Result<MaxDepth<List, 150>, PackError> = AsyncReadPackExt::unpack(&mut &list_bytes(150)[..]).await;
+        let v4: Result<MaxDepth<List, 1>, PackError> = AsyncReadPackExt::unpack(&mut &list_bytes(2)[..]).await;
+        let v5: Result<Box<MaxDepth<List, 1>>, PackError> = AsyncReadPackExt::unpack(&mut &list_bytes(2)[..]).await;
+
+        assert_eq!(v1.unwrap().depth(), DEFAULT_RECURSION_LIMIT);
+        assert_eq!(v2.err(), Some(PackError::RecursionLimit { max: DEFAULT_RECURSION_LIMIT }));
+        assert_eq!(v3.unwrap().0.depth(), 150);
+        assert_eq!(v4.err(), Some(PackError::RecursionLimit { max: 1 }));
+        assert_eq!(v5.err(), Some(PackError::RecursionLimit { max: 2 }));
+    }
+}
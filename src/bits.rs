@@ -0,0 +1,419 @@
+use core::cmp::Ordering;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::float::{dequantize, quantize, MAX_QUANTIZED_BITS};
+use crate::io::{Nesting, Read, Write};
+use crate::{try_array, PackError, Packable, Unpackable};
+
+// Shared by `write_quantized` and `read_quantized`, so both sides reject the same parameters
+fn check_quantization(min: f64, max: f64, bits: u32) -> Result<(), PackError> {
+    if bits == 0 || bits > MAX_QUANTIZED_BITS {
+        return Err(PackError::InvalidValue("quantization width must be 1 to 53 bits"));
+    }
+    if min.partial_cmp(&max) != Some(Ordering::Less) {
+        return Err(PackError::InvalidValue("quantization range is empty"));
+    }
+    Ok(())
+}
+
+/// Writes values at bit granularity. Bits are written most significant first, both within a
+/// value and within each byte, so whole bytes land on the wire the same as with `Packable`.
+pub struct BitWriter<W> {
+    inner: W,
+    acc: u8,
+    nbits: u32,
+}
+impl<W: Write> BitWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, acc: 0, nbits: 0 }
+    }
+
+    /// Writes the low `bits` bits of `value`. Fails if `value` doesn't fit in `bits` bits.
+    pub fn write_bits(&mut self, value: u64, bits: u32) -> Result<(), PackError> {
+        if bits > 64 || (bits < 64 && value >> bits != 0) {
+            return Err(PackError::InvalidValue("value does not fit in the bit width"));
+        }
+
+        let mut left = bits;
+        while left > 0 {
+            let free = 8 - self.nbits;
+            let take = free.min(left);
+            let chunk = ((value >> (left - take)) & ((1 << take) - 1)) as u8;
+
+            self.acc |= chunk << (free - take);
+            self.nbits += take;
+            left -= take;
+            if self.nbits == 8 {
+                self.inner.write_all(&[self.acc])?;
+                self.acc = 0;
+                self.nbits = 0;
+            }
+        }
+
+        Ok(())
+    }
+    pub fn write_bit(&mut self, bit: bool) -> Result<(), PackError> {
+        self.write_bits(bit as u64, 1)
+    }
+    /// Maps `value` from `min..=max` onto a `bits`-bit integer, rounding to the nearest step
+    pub fn write_quantized(&mut self, value: f64, min: f64, max: f64, bits: u32) -> Result<(), PackError> {
+        check_quantization(min, max, bits)?;
+        if !(min..=max).contains(&value) {
+            return Err(PackError::InvalidValue("value is outside the quantization range"));
+        }
+        self.write_bits(quantize(value, min, max - min, u64::MAX >> (64 - bits)), bits)
+    }
+    /// Pads the last partial byte with zero bits, writes it and returns the inner writer
+    pub fn finish(mut self) -> Result<W, PackError> {
+        if self.nbits > 0 {
+            self.inner.write_all(&[self.acc])?;
+        }
+        Ok(self.inner)
+    }
+}
+// Byte writes go through the bit stream, so byte-level `Packable`s can be embedded unaligned
+impl<W: Write> Write for BitWriter<W> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        if self.nbits == 0 {
+            return self.inner.write_all(buf);
+        }
+        for &b in buf {
+            self.write_bits(b.into(), 8)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads values written by `BitWriter`
+pub struct BitReader<R> {
+    inner: R,
+    acc: u8,
+    nbits: u32,
+}
+impl<R: Read> BitReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, acc: 0, nbits: 0 }
+    }
+
+    pub fn read_bits(&mut self, bits: u32) -> Result<u64, PackError> {
+        if bits > 64 {
+            return Err(PackError::InvalidValue("bit width exceeds 64"));
+        }
+
+        let mut v = 0u64;
+        let mut left = bits;
+        while left > 0 {
+            if self.nbits == 0 {
+                self.acc = u8::unpack_from(&mut self.inner)?;
+                self.nbits = 8;
+            }
+            let take = self.nbits.min(left);
+            let chunk = (self.acc >> (self.nbits - take)) & (((1u16 << take) - 1) as u8);
+
+            v = (v << take) | u64::from(chunk);
+            self.nbits -= take;
+            left -= take;
+        }
+
+        Ok(v)
+    }
+    pub fn read_bit(&mut self) -> Result<bool, PackError> {
+        Ok(self.read_bits(1)? != 0)
+    }
+    pub fn read_quantized(&mut self, min: f64, max: f64, bits: u32) -> Result<f64, PackError> {
+        check_quantization(min, max, bits)?;
+        Ok(dequantize(self.read_bits(bits)?, min, max - min, u64::MAX >> (64 - bits)))
+    }
+    /// Discards the rest of the current partial byte
+    pub fn align(&mut self) {
+        self.nbits = 0;
+    }
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: Read> Read for BitReader<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        if self.nbits == 0 {
+            return self.inner.read_exact(buf);
+        }
+        for b in buf {
+            *b = self.read_bits(8)? as u8;
+        }
+        Ok(())
+    }
+    fn nesting(&mut self) -> Option<&mut Nesting> {
+        self.inner.nesting()
+    }
+}
+
+pub trait BitPackable {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError>;
+    #[cfg(feature = "alloc")]
+    fn bit_pack(&self) -> Result<Vec<u8>, PackError> {
+        let mut wtr = BitWriter::new(Vec::new());
+        self.bit_pack_into(&mut wtr)?;
+        wtr.finish()
+    }
+}
+pub trait BitUnpackable where Self: Sized {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError>;
+    /// Unpacks from the start of `buf`, returning the bytes after the last one read from. Unused
+    /// bits of that last byte are dropped.
+    fn bit_unpack(buf: &[u8]) -> Result<(Self, &[u8]), PackError> {
+        let mut rdr = BitReader::new(buf);
+        let val = Self::bit_unpack_from(&mut rdr)?;
+        Ok((val, rdr.into_inner()))
+    }
+}
+
+impl BitPackable for bool {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        wtr.write_bit(*self)
+    }
+}
+impl BitUnpackable for bool {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        rdr.read_bit()
+    }
+}
+
+macro_rules! bit_unsigned {
+    ($($t:ty),*) => {$(
+        impl BitPackable for $t {
+            fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+                wtr.write_bits(*self as u64, <$t>::BITS)
+            }
+        }
+        impl BitUnpackable for $t {
+            fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+                Ok(rdr.read_bits(<$t>::BITS)? as $t)
+            }
+        }
+        impl<const N: u32> BitPackable for Bits<$t, N> {
+            fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+                if N > <$t>::BITS {
+                    return Err(PackError::InvalidValue("bit width exceeds the integer type"));
+                }
+                wtr.write_bits(self.0 as u64, N)
+            }
+        }
+        impl<const N: u32> BitUnpackable for Bits<$t, N> {
+            fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+                if N > <$t>::BITS {
+                    return Err(PackError::InvalidValue("bit width exceeds the integer type"));
+                }
+                Ok(Self(rdr.read_bits(N)? as $t))
+            }
+        }
+    )*};
+}
+bit_unsigned!(u8, u16, u32, u64);
+
+macro_rules! bit_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl BitPackable for $t {
+            fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+                (*self as $u).bit_pack_into(wtr)
+            }
+        }
+        impl BitUnpackable for $t {
+            fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+                Ok(<$u>::bit_unpack_from(rdr)? as $t)
+            }
+        }
+        impl<const N: u32> BitPackable for Bits<$t, N> {
+            fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+                if N == 0 || N > <$t>::BITS {
+                    return Err(PackError::InvalidValue("bit width exceeds the integer type"));
+                }
+                // In i128 so the bounds of a full-width i64 don't overflow
+                let v = i128::from(self.0);
+                if v < -(1 << (N - 1)) || v >= 1 << (N - 1) {
+                    return Err(PackError::InvalidValue("value does not fit in the bit width"));
+                }
+                wtr.write_bits(v as u64 & (u64::MAX >> (64 - N)), N)
+            }
+        }
+        impl<const N: u32> BitUnpackable for Bits<$t, N> {
+            fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+                if N == 0 || N > <$t>::BITS {
+                    return Err(PackError::InvalidValue("bit width exceeds the integer type"));
+                }
+                // Sign-extend from N bits
+                let v = ((rdr.read_bits(N)? << (64 - N)) as i64) >> (64 - N);
+                Ok(Self(v as $t))
+            }
+        }
+    )*};
+}
+bit_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64);
+
+impl BitPackable for f32 {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        self.to_bits().bit_pack_into(wtr)
+    }
+}
+impl BitUnpackable for f32 {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        Ok(f32::from_bits(u32::bit_unpack_from(rdr)?))
+    }
+}
+impl BitPackable for f64 {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        self.to_bits().bit_pack_into(wtr)
+    }
+}
+impl BitUnpackable for f64 {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        Ok(f64::from_bits(u64::bit_unpack_from(rdr)?))
+    }
+}
+
+impl<T: BitPackable, const S: usize> BitPackable for [T; S] {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        for v in self {
+            v.bit_pack_into(wtr)?;
+        }
+
+        Ok(())
+    }
+}
+impl<T: BitUnpackable, const S: usize> BitUnpackable for [T; S] {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        try_array(|| T::bit_unpack_from(rdr))
+    }
+}
+
+/// An integer packed into exactly `N` bits. Signed values are stored as `N`-bit two's complement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bits<T, const N: u32>(pub T);
+
+/// Embeds a byte-level `Packable` in a bit stream, starting at the current bit position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Packed<T>(pub T);
+
+impl<T: Packable> BitPackable for Packed<T> where PackError: From<T::Error> {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        Ok(self.0.pack_into(wtr)?)
+    }
+}
+impl<T: Unpackable> BitUnpackable for Packed<T> where PackError: From<T::Error> {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        Ok(Self(T::unpack_from(rdr)?))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_bit_writer() {
+        let mut wtr = BitWriter::new(Vec::new());
+        wtr.write_bit(true).unwrap();
+        wtr.write_bits(0b010, 3).unwrap();
+        wtr.write_bits(0xabc, 12).unwrap();
+        wtr.write_bit(true).unwrap();
+
+        assert_eq!(wtr.write_bits(4, 2), Err(PackError::InvalidValue("value does not fit in the bit width")));
+        assert_eq!(wtr.finish().unwrap(), vec![0b1010_1010, 0b1011_1100, 0b1000_0000]);
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        let i = [0b1010_1010, 0b1011_1100, 0b1000_0000];
+        let mut rdr = BitReader::new(&i[..]);
+
+        assert!(rdr.read_bit().unwrap());
+        assert_eq!(rdr.read_bits(3).unwrap(), 0b010);
+        assert_eq!(rdr.read_bits(12).unwrap(), 0xabc);
+        assert!(rdr.read_bit().unwrap());
+        assert_eq!(rdr.read_bits(7).unwrap(), 0);
+        assert_eq!(rdr.read_bit(), Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_pack_bits() {
+        let v = (Bits::<u8, 3>(5), true, Bits::<i16, 5>(-3), Bits::<u32, 7>(0x7f));
+
+        let mut wtr = BitWriter::new(Vec::new());
+        v.0.bit_pack_into(&mut wtr).unwrap();
+        v.1.bit_pack_into(&mut wtr).unwrap();
+        v.2.bit_pack_into(&mut wtr).unwrap();
+        v.3.bit_pack_into(&mut wtr).unwrap();
+
+        assert_eq!(wtr.finish().unwrap(), vec![0b1011_1110, 0b1111_1111]);
+        assert_eq!(Bits::<u8, 3>(8).bit_pack(), Err(PackError::InvalidValue("value does not fit in the bit width")));
+        assert_eq!(Bits::<i8, 3>(4).bit_pack(), Err(PackError::InvalidValue("value does not fit in the bit width")));
+        assert_eq!(Bits::<u8, 9>(0).bit_pack(), Err(PackError::InvalidValue("bit width exceeds the integer type")));
+    }
+
+    #[test]
+    fn test_roundtrip_bits() {
+        let i = [0b1011_1110, 0b1111_1111, 0xaa];
+        let mut rdr = BitReader::new(&i[..]);
+
+        assert_eq!(Bits::<u8, 3>::bit_unpack_from(&mut rdr).unwrap(), Bits(5));
+        assert!(bool::bit_unpack_from(&mut rdr).unwrap());
+        assert_eq!(Bits::<i16, 5>::bit_unpack_from(&mut rdr).unwrap(), Bits(-3));
+        assert_eq!(Bits::<u32, 7>::bit_unpack_from(&mut rdr).unwrap(), Bits(0x7f));
+        assert_eq!(rdr.into_inner(), &[0xaa]);
+
+        let w = (Bits::<i64, 64>(i64::MIN), Bits::<i64, 64>(i64::MAX), Bits::<u64, 64>(u64::MAX));
+        let mut wtr = BitWriter::new(Vec::new());
+        w.0.bit_pack_into(&mut wtr).unwrap();
+        w.1.bit_pack_into(&mut wtr).unwrap();
+        w.2.bit_pack_into(&mut wtr).unwrap();
+        let i = wtr.finish().unwrap();
+        let mut rdr = BitReader::new(&i[..]);
+
+        assert_eq!(&i[..8], &i64::MIN.to_be_bytes());
+        assert_eq!(Bits::<i64, 64>::bit_unpack_from(&mut rdr).unwrap(), w.0);
+        assert_eq!(Bits::<i64, 64>::bit_unpack_from(&mut rdr).unwrap(), w.1);
+        assert_eq!(Bits::<u64, 64>::bit_unpack_from(&mut rdr).unwrap(), w.2);
+
+        let a = [-1i32, 7].bit_pack().unwrap();
+        let (t, tb) = <[i32; 2]>::bit_unpack(&a).unwrap();
+        assert_eq!(t, [-1, 7]);
+        assert_eq!(tb, &[]);
+    }
+
+    #[test]
+    fn test_roundtrip_quantized_bits() {
+        let mut wtr = BitWriter::new(Vec::new());
+        wtr.write_quantized(0.25, -1.0, 1.0, 10).unwrap();
+        wtr.write_quantized(-1.0, -1.0, 1.0, 10).unwrap();
+
+        assert_eq!(wtr.write_quantized(1.5, -1.0, 1.0, 10), Err(PackError::InvalidValue("value is outside the quantization range")));
+        assert_eq!(wtr.write_quantized(0.0, -1.0, 1.0, 54), Err(PackError::InvalidValue("quantization width must be 1 to 53 bits")));
+        assert_eq!(wtr.write_quantized(1.0, 1.0, 1.0, 10), Err(PackError::InvalidValue("quantization range is empty")));
+
+        let i = wtr.finish().unwrap();
+        let mut rdr = BitReader::new(&i[..]);
+
+        assert_eq!(i.len(), 3);
+        assert!((rdr.read_quantized(-1.0, 1.0, 10).unwrap() - 0.25).abs() <= 1.0 / 1023.0);
+        assert_eq!(rdr.read_quantized(-1.0, 1.0, 10).unwrap(), -1.0);
+        assert_eq!(rdr.read_quantized(-1.0, 1.0, 0), Err(PackError::InvalidValue("quantization width must be 1 to 53 bits")));
+        assert_eq!(rdr.read_quantized(1.0, -1.0, 4), Err(PackError::InvalidValue("quantization range is empty")));
+        assert_eq!(rdr.read_quantized(f64::NAN, 1.0, 4), Err(PackError::InvalidValue("quantization range is empty")));
+    }
+
+    #[test]
+    fn test_roundtrip_packed_in_bits() {
+        let mut wtr = BitWriter::new(Vec::new());
+        true.bit_pack_into(&mut wtr).unwrap();
+        Packed(0x0102u16).bit_pack_into(&mut wtr).unwrap();
+        Packed(LenPrefixed::<u8, &str>::new("a")).bit_pack_into(&mut wtr).unwrap();
+
+        let i = wtr.finish().unwrap();
+        let mut rdr = BitReader::new(&i[..]);
+
+        assert_eq!(i, vec![0x80, 0x81, 0x00, 0xb0, 0x80]);
+        assert!(bool::bit_unpack_from(&mut rdr).unwrap());
+        assert_eq!(Packed::<u16>::bit_unpack_from(&mut rdr).unwrap(), Packed(0x0102));
+        assert_eq!(Packed::<LenPrefixed<u8, String>>::bit_unpack_from(&mut rdr).unwrap().0.as_str(), "a");
+    }
+}
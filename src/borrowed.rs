@@ -0,0 +1,132 @@
+use core::convert::TryInto;
+
+use crate::len::{unpack_len, LengthPrefix};
+use crate::string::str_from_utf8;
+use crate::{FixedWidth, LenPrefixed, NulTerminated, PackError, Unpackable};
+
+/// Unpacks a value that may borrow from the input buffer `'de` instead of copying out of it.
+/// Every `Unpackable` type implements this by unpacking an owned value.
+pub trait UnpackBorrowed<'de>: Sized {
+    /// Unpacks from the start of `buf` and advances it past the bytes consumed
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError>;
+}
+
+pub fn unpack_borrowed<'de, T: UnpackBorrowed<'de>>(buf: &'de [u8]) -> Result<(T, &'de [u8]), PackError> {
+    let mut rest = buf;
+    let val = T::unpack_borrowed(&mut rest)?;
+    Ok((val, rest))
+}
+
+fn take<'de>(buf: &mut &'de [u8], len: usize) -> Result<&'de [u8], PackError> {
+    if buf.len() < len {
+        return Err(PackError::SizeError);
+    }
+    let (head, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(head)
+}
+
+impl<'de, T: Unpackable> UnpackBorrowed<'de> for T where PackError: From<T::Error> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        Ok(T::unpack_from(buf)?)
+    }
+}
+
+impl<'de: 'a, 'a, const N: usize> UnpackBorrowed<'de> for &'a [u8; N] {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        Ok(take(buf, N)?.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+}
+
+impl<'de: 'a, 'a, L: LengthPrefix> UnpackBorrowed<'de> for LenPrefixed<L, &'a [u8]> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let len = unpack_len::<L>(buf)?;
+        Ok(Self::new(take(buf, len)?))
+    }
+}
+impl<'de: 'a, 'a, L: LengthPrefix> UnpackBorrowed<'de> for LenPrefixed<L, &'a str> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let bytes = LenPrefixed::<L, &[u8]>::unpack_borrowed(buf)?.into_inner();
+        Ok(Self::new(str_from_utf8(bytes)?))
+    }
+}
+
+impl<'de: 'a, 'a> UnpackBorrowed<'de> for NulTerminated<&'a [u8]> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let max = Self::MAX_LEN;
+        let len = match buf.iter().take(max + 1).position(|&b| b == 0) {
+            Some(len) => len,
+            None if buf.len() > max => {
+                return Err(PackError::LengthLimit { len: max as u64 + 1, max: max as u64 });
+            }
+            None => return Err(PackError::SizeError),
+        };
+        let bytes = take(buf, len)?;
+        *buf = &buf[1..];
+        Ok(Self(bytes))
+    }
+}
+impl<'de: 'a, 'a> UnpackBorrowed<'de> for NulTerminated<&'a str> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let bytes = NulTerminated::<&[u8]>::unpack_borrowed(buf)?.0;
+        Ok(Self(str_from_utf8(bytes)?))
+    }
+}
+
+impl<'de: 'a, 'a, const N: usize> UnpackBorrowed<'de> for FixedWidth<&'a [u8], N> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let bytes = take(buf, N)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(N);
+        Ok(Self(&bytes[..end]))
+    }
+}
+impl<'de: 'a, 'a, const N: usize> UnpackBorrowed<'de> for FixedWidth<&'a str, N> {
+    fn unpack_borrowed(buf: &mut &'de [u8]) -> Result<Self, PackError> {
+        let bytes = FixedWidth::<&[u8], N>::unpack_borrowed(buf)?.0;
+        Ok(Self(str_from_utf8(bytes)?))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_unpack_borrowed_slices() {
+        let i = [2, b'h', b'i', 1, 2, 3, b'a', 0, b'b', 0, 0, 0xff];
+
+        let mut rest = &i[..];
+        let v1: LenPrefixed<u8, &str> = UnpackBorrowed::unpack_borrowed(&mut rest).unwrap();
+        let v2: &[u8; 3] = UnpackBorrowed::unpack_borrowed(&mut rest).unwrap();
+        let v3: NulTerminated<&str> = UnpackBorrowed::unpack_borrowed(&mut rest).unwrap();
+        let v4: FixedWidth<&[u8], 3> = UnpackBorrowed::unpack_borrowed(&mut rest).unwrap();
+
+        assert_eq!(*v1, "hi");
+        assert_eq!(v1.as_ptr(), i[1..].as_ptr());
+        assert_eq!(v2, &[1, 2, 3]);
+        assert_eq!(v3.0, "a");
+        assert_eq!(v4.0, b"b");
+        assert_eq!(rest, &[0xff]);
+    }
+
+    #[test]
+    fn test_unpack_borrowed_errors() {
+        let v1: Result<(LenPrefixed<u8, &[u8]>, _), PackError> = unpack_borrowed(&[3, 1, 2]);
+        let v2: Result<(LenPrefixed<u8, &str>, _), PackError> = unpack_borrowed(&[1, 0xff]);
+        let v3: Result<(NulTerminated<&[u8]>, _), PackError> = unpack_borrowed(&[1, 2]);
+        let v4: Result<(&[u8; 2], _), PackError> = unpack_borrowed(&[1]);
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Err(PackError::InvalidValue("string is not valid utf-8")));
+        assert_eq!(v3, Err(PackError::SizeError));
+        assert_eq!(v4, Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_unpack_borrowed_owned() {
+        let (v, b): (u32, _) = unpack_borrowed(&[0, 0, 1, 0, 0xff]).unwrap();
+
+        assert_eq!(v, 256);
+        assert_eq!(b, &[0xff]);
+    }
+}
@@ -0,0 +1,170 @@
+use core::marker::PhantomData;
+
+use bytes::{BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{unpack, LengthPrefix, PackError, Packable, Unpackable};
+
+/// Frames each `T` as its packed length, encoded as `L`, followed by the packed value
+#[derive(Debug)]
+pub struct NetpackCodec<T, L = u32> {
+    max_frame_len: usize,
+    _marker: PhantomData<fn() -> (T, L)>,
+}
+impl<T, L> NetpackCodec<T, L> {
+    pub const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self::with_max_frame_len(Self::DEFAULT_MAX_FRAME_LEN)
+    }
+    pub fn with_max_frame_len(max_frame_len: usize) -> Self {
+        Self { max_frame_len, _marker: PhantomData }
+    }
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+    pub fn set_max_frame_len(&mut self, max_frame_len: usize) {
+        self.max_frame_len = max_frame_len;
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), PackError> {
+        if len > self.max_frame_len {
+            return Err(PackError::LengthLimit { len: len as u64, max: self.max_frame_len as u64 });
+        }
+        Ok(())
+    }
+}
+impl<T, L> Default for NetpackCodec<T, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T, L> Clone for NetpackCodec<T, L> {
+    fn clone(&self) -> Self {
+        Self::with_max_frame_len(self.max_frame_len)
+    }
+}
+
+impl<T, L> Encoder<T> for NetpackCodec<T, L>
+where
+    T: Packable,
+    L: LengthPrefix,
+    PackError: From<T::Error>,
+{
+    type Error = PackError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), PackError> {
+        let frame = item.pack()?;
+        self.check_len(frame.len())?;
+        let header = L::from_len(frame.len())?.pack()?;
+
+        dst.reserve(header.len() + frame.len());
+        dst.put_slice(&header);
+        dst.put_slice(&frame);
+        Ok(())
+    }
+}
+
+impl<T, L> Decoder for NetpackCodec<T, L>
+where
+    T: Unpackable,
+    L: LengthPrefix,
+    PackError: From<T::Error>,
+{
+    type Item = T;
+    type Error = PackError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, PackError> {
+        let (len, rest) = match unpack::<L>(src) {
+            Ok(v) => v,
+            // The length prefix itself hasn't fully arrived
+            Err(PackError::SizeError) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let len = len.to_len()?;
+        self.check_len(len)?;
+
+        let header = src.len() - rest.len();
+        if rest.len() < len {
+            src.reserve(len - rest.len());
+            return Ok(None);
+        }
+
+        let frame = src.split_to(header + len);
+        let (item, rest) = unpack::<T>(&frame[header..])?;
+        if !rest.is_empty() {
+            return Err(PackError::InvalidValue("frame has trailing bytes"));
+        }
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn test_encode() {
+        let mut codec = NetpackCodec::<u16, u8>::new();
+        let mut buf = BytesMut::new();
+
+        codec.encode(0x1234, &mut buf).unwrap();
+        codec.encode(0x5678, &mut buf).unwrap();
+
+        assert_eq!(&buf[..], &[2, 0x12, 0x34, 2, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn test_decode_partial() {
+        let mut codec = NetpackCodec::<[u16; 2], u16>::new();
+        let mut buf = BytesMut::new();
+
+        buf.extend_from_slice(&[0]);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(&[4, 0, 1, 0]);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        assert_eq!(buf.len(), 5);
+        buf.extend_from_slice(&[2, 0, 4]);
+        assert_eq!(codec.decode(&mut buf), Ok(Some([1, 2])));
+        assert_eq!(&buf[..], &[0, 4]);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+    }
+
+    #[test]
+    fn test_codec_errors() {
+        let mut codec = NetpackCodec::<u8, VarU64>::with_max_frame_len(4);
+        let mut buf = BytesMut::new();
+
+        assert_eq!(codec.encode(4, &mut buf), Ok(()));
+        assert_eq!(codec.decode(&mut buf), Ok(Some(4)));
+
+        buf.extend_from_slice(&[5]);
+        assert_eq!(codec.decode(&mut buf), Err(PackError::LengthLimit { len: 5, max: 4 }));
+
+        let mut buf = BytesMut::from(&[2, 1, 2][..]);
+        assert_eq!(codec.decode(&mut buf), Err(PackError::InvalidValue("frame has trailing bytes")));
+
+        let mut codec = NetpackCodec::<[u8; 5], u8>::with_max_frame_len(4);
+        assert_eq!(codec.encode([0; 5], &mut buf), Err(PackError::LengthLimit { len: 5, max: 4 }));
+    }
+
+    #[tokio::test]
+    async fn test_codec_framed() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_util::codec::Framed;
+
+        let (client, server) = tokio::io::duplex(8);
+        let mut client = Framed::new(client, NetpackCodec::<LenPrefixed<u8, String>, VarU64>::new());
+        let mut server = Framed::new(server, NetpackCodec::<LenPrefixed<u8, String>, VarU64>::new());
+
+        let msg: LenPrefixed<u8, String> = String::from("a message longer than the pipe").into();
+        let send = client.send(msg.clone());
+        let recv = server.next();
+        let (sent, received) = tokio::join!(send, recv);
+
+        sent.unwrap();
+        assert_eq!(received.unwrap(), Ok(msg));
+    }
+}
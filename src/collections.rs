@@ -0,0 +1,399 @@
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+use crate::io::{Read, Write};
+#[cfg(feature = "std")]
+use crate::len::MAX_PREALLOC;
+use crate::len::{len_size, pack_len, unpack_len};
+use crate::size::len_sum;
+use crate::{LenPrefixed, LengthPrefix, PackError, Packable, PackedSize, Unpackable};
+
+const DUPLICATE_KEY: PackError = PackError::InvalidValue("duplicate map key");
+const DUPLICATE_ELEMENT: PackError = PackError::InvalidValue("duplicate set element");
+
+/// Packs a hashed collection in ascending key order, so equal collections always pack to the same
+/// bytes. Used as `LenPrefixed<L, Sorted<HashMap<K, V>>>`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sorted<C>(pub C);
+impl<C> Deref for Sorted<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.0
+    }
+}
+impl<C> DerefMut for Sorted<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.0
+    }
+}
+
+fn pack_entries<'a, L, K, V, I>(len: usize, entries: I, stream: &mut impl Write) -> Result<(), PackError>
+where
+    L: LengthPrefix,
+    K: Packable + 'a,
+    V: Packable + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    pack_len::<L>(len, stream)?;
+    for (k, v) in entries {
+        k.pack_into(stream)?;
+        v.pack_into(stream)?;
+    }
+    Ok(())
+}
+fn pack_elements<'a, L, T, I>(len: usize, elements: I, stream: &mut impl Write) -> Result<(), T::Error>
+where
+    L: LengthPrefix,
+    T: Packable + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    pack_len::<L>(len, stream)?;
+    for v in elements {
+        v.pack_into(stream)?;
+    }
+    Ok(())
+}
+
+impl<L, K, V> Packable for LenPrefixed<L, BTreeMap<K, V>>
+where
+    L: LengthPrefix,
+    K: Packable,
+    V: Packable,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_entries::<L, _, _, _>(self.len(), self.iter(), stream)
+    }
+}
+impl<L, K, V> Unpackable for LenPrefixed<L, BTreeMap<K, V>>
+where
+    L: LengthPrefix,
+    K: Unpackable + Ord,
+    V: Unpackable,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        let mut map = BTreeMap::new();
+
+        for _ in 0..len {
+            if map.insert(K::unpack_from(rdr)?, V::unpack_from(rdr)?).is_some() {
+                return Err(DUPLICATE_KEY);
+            }
+        }
+
+        Ok(Self::new(map))
+    }
+}
+
+impl<L: LengthPrefix, T: Packable> Packable for LenPrefixed<L, BTreeSet<T>> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_elements::<L, _, _>(self.len(), self.iter(), stream)
+    }
+}
+impl<L: LengthPrefix, T: Unpackable + Ord> Unpackable for LenPrefixed<L, BTreeSet<T>> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        let mut set = BTreeSet::new();
+
+        for _ in 0..len {
+            if !set.insert(T::unpack_from(rdr)?) {
+                return Err(DUPLICATE_ELEMENT.into());
+            }
+        }
+
+        Ok(Self::new(set))
+    }
+}
+
+impl<L: LengthPrefix, T: Packable> Packable for LenPrefixed<L, VecDeque<T>> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_elements::<L, _, _>(self.len(), self.iter(), stream)
+    }
+}
+impl<L: LengthPrefix, T: Unpackable> Unpackable for LenPrefixed<L, VecDeque<T>> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let v = LenPrefixed::<L, Vec<T>>::unpack_from(rdr)?;
+        Ok(Self::new(v.into_inner().into()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L, K, V, S> Packable for LenPrefixed<L, HashMap<K, V, S>>
+where
+    L: LengthPrefix,
+    K: Packable,
+    V: Packable,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_entries::<L, _, _, _>(self.len(), self.iter(), stream)
+    }
+}
+#[cfg(feature = "std")]
+impl<L, K, V, S> Unpackable for LenPrefixed<L, HashMap<K, V, S>>
+where
+    L: LengthPrefix,
+    K: Unpackable + Eq + Hash,
+    V: Unpackable,
+    S: BuildHasher + Default,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        let mut map = HashMap::with_capacity_and_hasher(len.min(MAX_PREALLOC), S::default());
+
+        for _ in 0..len {
+            if map.insert(K::unpack_from(rdr)?, V::unpack_from(rdr)?).is_some() {
+                return Err(DUPLICATE_KEY);
+            }
+        }
+
+        Ok(Self::new(map))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: LengthPrefix, T: Packable, S> Packable for LenPrefixed<L, HashSet<T, S>> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_elements::<L, _, _>(self.len(), self.iter(), stream)
+    }
+}
+#[cfg(feature = "std")]
+impl<L, T, S> Unpackable for LenPrefixed<L, HashSet<T, S>>
+where
+    L: LengthPrefix,
+    T: Unpackable + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        let mut set = HashSet::with_capacity_and_hasher(len.min(MAX_PREALLOC), S::default());
+
+        for _ in 0..len {
+            if !set.insert(T::unpack_from(rdr)?) {
+                return Err(DUPLICATE_ELEMENT.into());
+            }
+        }
+
+        Ok(Self::new(set))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L, K, V, S> Packable for LenPrefixed<L, Sorted<HashMap<K, V, S>>>
+where
+    L: LengthPrefix,
+    K: Packable + Ord,
+    V: Packable,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        pack_entries::<L, _, _, _>(entries.len(), entries, stream)
+    }
+}
+#[cfg(feature = "std")]
+impl<L, K, V, S> Unpackable for LenPrefixed<L, Sorted<HashMap<K, V, S>>>
+where
+    L: LengthPrefix,
+    K: Unpackable + Eq + Hash,
+    V: Unpackable,
+    S: BuildHasher + Default,
+    PackError: From<K::Error> + From<V::Error>,
+{
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let map = LenPrefixed::<L, HashMap<K, V, S>>::unpack_from(rdr)?;
+        Ok(Self::new(Sorted(map.into_inner())))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: LengthPrefix, T: Packable + Ord, S> Packable for LenPrefixed<L, Sorted<HashSet<T, S>>> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let mut elements: Vec<_> = self.iter().collect();
+        elements.sort_unstable();
+        pack_elements::<L, _, _>(elements.len(), elements, stream)
+    }
+}
+#[cfg(feature = "std")]
+impl<L, T, S> Unpackable for LenPrefixed<L, Sorted<HashSet<T, S>>>
+where
+    L: LengthPrefix,
+    T: Unpackable + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let set = LenPrefixed::<L, HashSet<T, S>>::unpack_from(rdr)?;
+        Ok(Self::new(Sorted(set.into_inner())))
+    }
+}
+
+// Maps pack each key followed by its value, sets and sequences each element
+macro_rules! collection_size {
+    ($(($($g:ident),* $(; $s:ident)?) $c:ty;)*) => {$(
+        impl<L: LengthPrefix + PackedSize, $($g: PackedSize),* $(, $s)?> PackedSize for LenPrefixed<L, $c> {
+            const SIZE: Option<usize> = None;
+
+            fn packed_len(&self) -> usize {
+                len_size::<L>(self.len()).saturating_add(len_sum(self.iter()))
+            }
+        }
+    )*};
+}
+collection_size! {
+    (K, V) BTreeMap<K, V>;
+    (T) BTreeSet<T>;
+    (T) VecDeque<T>;
+}
+#[cfg(feature = "std")]
+collection_size! {
+    (K, V; S) HashMap<K, V, S>;
+    (T; S) HashSet<T, S>;
+    (K, V; S) Sorted<HashMap<K, V, S>>;
+    (T; S) Sorted<HashSet<T, S>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+    #[cfg(feature = "std")]
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_pack_collections() {
+        let v1: LenPrefixed<u8, BTreeMap<u8, u16>> = BTreeMap::from([(2, 0x0304), (1, 0x0102)]).into();
+        let v2: LenPrefixed<u16, BTreeSet<u8>> = BTreeSet::from([3, 1, 2]).into();
+        let v3: LenPrefixed<u8, VecDeque<u8>> = VecDeque::from(vec![3, 1, 2]).into();
+
+        assert_eq!(v1.pack().unwrap(), vec![2, 1, 0x01, 0x02, 2, 0x03, 0x04]);
+        assert_eq!(v2.pack().unwrap(), vec![0, 3, 1, 2, 3]);
+        assert_eq!(v3.pack().unwrap(), vec![3, 3, 1, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pack_hashed_collections() {
+        let v1: LenPrefixed<u8, HashSet<u8>> = HashSet::new().into();
+
+        assert_eq!(v1.pack().unwrap(), vec![0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pack_sorted() {
+        let keys: Vec<u16> = (0..64).rev().collect();
+        let map: HashMap<u16, bool> = keys.iter().map(|&k| (k, k % 2 == 0)).collect();
+        let set: HashSet<u16> = keys.iter().copied().collect();
+
+        let m1 = LenPrefixed::<u8, _>::new(Sorted(map.clone())).pack().unwrap();
+        let m2 = LenPrefixed::<u8, _>::new(map.into_iter().collect::<BTreeMap<_, _>>()).pack().unwrap();
+        let s1 = LenPrefixed::<u8, _>::new(Sorted(set.clone())).pack().unwrap();
+        let s2 = LenPrefixed::<u8, _>::new(set.into_iter().collect::<BTreeSet<_>>()).pack().unwrap();
+
+        assert_eq!(m1, m2);
+        assert_eq!(s1, s2);
+    }
+
+    // Packs `v` and unpacks it again, checking nothing is left over
+    fn roundtrip<T: Packable<Error = PackError> + Unpackable<Error = PackError>>(v: &T) -> T {
+        let i = v.pack().unwrap();
+        let (t, tb) = unpack::<T>(&i).unwrap();
+        assert_eq!(tb, &[]);
+        t
+    }
+
+    #[test]
+    fn test_roundtrip_collections() {
+        let v1: LenPrefixed<u8, BTreeMap<u8, LenPrefixed<u8, String>>> =
+            BTreeMap::from([(1, String::from("a").into()), (2, String::from("bc").into())]).into();
+        let v2: LenPrefixed<u8, VecDeque<u16>> = VecDeque::from(vec![1, 2]).into();
+
+        assert_eq!(roundtrip(&v1), v1);
+        assert_eq!(roundtrip(&v2), v2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_roundtrip_hashed_collections() {
+        let v1: LenPrefixed<u8, HashMap<u8, LenPrefixed<u8, String>>> =
+            HashMap::from([(1, String::from("a").into()), (2, String::from("bc").into())]).into();
+        let v2: LenPrefixed<VarU64, Sorted<HashSet<i32>>> = LenPrefixed::new(Sorted(HashSet::from([-1, 5, 300])));
+
+        assert_eq!(roundtrip(&v1), v1);
+        assert_eq!(roundtrip(&v2), v2);
+    }
+
+    #[test]
+    fn test_packed_len_collections() {
+        let v1: LenPrefixed<u8, _> = BTreeMap::from([(1u8, VarU64(300)), (2, VarU64(1))]).into();
+
+        assert_eq!(v1.packed_len(), v1.pack().unwrap().len());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_packed_len_hashed_collections() {
+        let v1: LenPrefixed<u16, _> = Sorted(HashSet::from([1u32, 2, 3])).into();
+
+        assert_eq!(v1.packed_len(), 14);
+    }
+
+    #[test]
+    fn test_unpack_collections_errors() {
+        let v1 = unpack::<LenPrefixed<u8, BTreeMap<u8, u8>>>(&[2, 1, 0, 1, 0]).err();
+        let v2 = unpack::<LenPrefixed<u8, BTreeSet<u8>>>(&[3, 7, 8]).err();
+        let v3 = unpack::<LenPrefixed<u8, BTreeSet<u8>>>(&[2, 7, 7]).err();
+
+        assert_eq!(v1, Some(PackError::InvalidValue("duplicate map key")));
+        assert_eq!(v2, Some(PackError::SizeError));
+        assert_eq!(v3, Some(PackError::InvalidValue("duplicate set element")));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_unpack_hashed_collections_errors() {
+        let v1 = unpack::<LenPrefixed<u8, HashMap<u8, u8>>>(&[2, 1, 0, 1, 0]).err();
+        let v2 = unpack::<LenPrefixed<u8, HashSet<u8>>>(&[2, 7, 7]).err();
+
+        assert_eq!(v1, Some(PackError::InvalidValue("duplicate map key")));
+        assert_eq!(v2, Some(PackError::InvalidValue("duplicate set element")));
+    }
+}
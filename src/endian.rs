@@ -0,0 +1,169 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::io::{Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::{try_array, PackError, Packable, PackedSize, Unpackable};
+
+/// Types that can be packed in either byte order. The plain `Packable`/`Unpackable` impls of these
+/// types always use network (big endian) order.
+pub trait EndianPackable: Sized {
+    fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError>;
+    fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError>;
+}
+
+macro_rules! endian_single_byte {
+    ($($t:ty),*) => {$(
+        impl EndianPackable for $t {
+            fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+                self.pack_into(stream)
+            }
+            fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+                Self::unpack_from(rdr)
+            }
+        }
+    )*};
+}
+endian_single_byte!(bool, u8, i8);
+
+macro_rules! endian_packable {
+    ($($t:ty => $write:ident, $read:ident;)*) => {$(
+        impl EndianPackable for $t {
+            fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+                stream.$write::<E>(*self)?;
+                Ok(())
+            }
+            fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+                rdr.$read::<E>()
+            }
+        }
+    )*};
+}
+endian_packable! {
+    u16 => write_u16, read_u16;
+    i16 => write_i16, read_i16;
+    u32 => write_u32, read_u32;
+    i32 => write_i32, read_i32;
+    u64 => write_u64, read_u64;
+    i64 => write_i64, read_i64;
+    u128 => write_u128, read_u128;
+    i128 => write_i128, read_i128;
+    f32 => write_f32, read_f32;
+    f64 => write_f64, read_f64;
+}
+
+impl<T: EndianPackable, const S: usize> EndianPackable for [T; S] {
+    fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+        for v in self {
+            v.pack_into_endian::<E>(stream)?;
+        }
+
+        Ok(())
+    }
+    fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+        try_array(|| T::unpack_from_endian::<E>(rdr))
+    }
+}
+
+/// Packs the wrapped value in little endian order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Le<T>(pub T);
+
+impl<T: EndianPackable> Packable for Le<T> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into_endian::<LittleEndian>(stream)
+    }
+}
+impl<T: EndianPackable> Unpackable for Le<T> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(T::unpack_from_endian::<LittleEndian>(rdr)?))
+    }
+}
+
+/// Packs the wrapped value in big endian order, the same as the unwrapped value. Useful for
+/// spelling out the byte order next to `Le` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Be<T>(pub T);
+
+impl<T: EndianPackable> Packable for Be<T> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into_endian::<BigEndian>(stream)
+    }
+}
+impl<T: EndianPackable> Unpackable for Be<T> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(T::unpack_from_endian::<BigEndian>(rdr)?))
+    }
+}
+
+impl<T: PackedSize> PackedSize for Le<T> {
+    const SIZE: Option<usize> = T::SIZE;
+
+    fn packed_len(&self) -> usize {
+        self.0.packed_len()
+    }
+}
+impl<T: PackedSize> PackedSize for Be<T> {
+    const SIZE: Option<usize> = T::SIZE;
+
+    fn packed_len(&self) -> usize {
+        self.0.packed_len()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_pack_le() {
+        assert_eq!(Le(0x0123u16).pack().unwrap(), vec![0x23, 0x01]);
+        assert_eq!(Le(-2i32).pack().unwrap(), vec![0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(Le(1.0f32).pack().unwrap(), vec![0x00, 0x00, 0x80, 0x3f]);
+        assert_eq!(Le([0x0102u16, 0x0304]).pack().unwrap(), vec![0x02, 0x01, 0x04, 0x03]);
+        assert_eq!(Le(0x12u8).pack().unwrap(), vec![0x12]);
+    }
+
+    #[test]
+    fn test_unpack_le() {
+        let i1 = [0x23];
+        let i2 = [0x23, 0x01, 0xff];
+
+        let v1: Result<(Le<u16>, _), PackError> = unpack(&i1);
+        let (v2, b2): (Le<u16>, _) = unpack(&i2).unwrap();
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Le(0x0123));
+        assert_eq!(b2, &[0xff]);
+    }
+
+    #[test]
+    fn test_pack_be() {
+        assert_eq!(Be(0x0123u16).pack().unwrap(), 0x0123u16.pack().unwrap());
+        assert_eq!(Be(-5i128).pack().unwrap(), (-5i128).pack().unwrap());
+    }
+
+    #[test]
+    fn test_roundtrip_endian() {
+        let v1 = Le(0x0123_4567_89ab_cdefu64);
+        let v2 = Be([1.5f64, -2.25]);
+
+        let i1 = v1.pack().unwrap();
+        let i2 = v2.pack().unwrap();
+
+        let (t1, b1): (Le<u64>, _) = unpack(&i1).unwrap();
+        let (t2, b2): (Be<[f64; 2]>, _) = unpack(&i2).unwrap();
+
+        assert_eq!(i1, vec![0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]);
+        assert_eq!(t1, v1);
+        assert_eq!(b1, &[]);
+        assert_eq!(t2, v2);
+        assert_eq!(b2, &[]);
+    }
+}
@@ -0,0 +1,472 @@
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::io::{Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::{BitPackable, BitReader, BitUnpackable, BitWriter, EndianPackable, PackError, Packable, PackedSize, Unpackable};
+
+const OUT_OF_RANGE: PackError = PackError::InvalidValue("value is out of range");
+const NOT_A_NUMBER: PackError = PackError::InvalidValue("value is not a number");
+
+// Rounds half away from zero. `x` must be within the range of an `i128`; `f64::round` isn't
+// available without std.
+fn round(x: f64) -> i128 {
+    let t = x as i128;
+    let frac = x - t as f64;
+    if frac >= 0.5 {
+        t + 1
+    } else if frac <= -0.5 {
+        t - 1
+    } else {
+        t
+    }
+}
+
+// Widest quantization whose step indices are all exact in an `f64`
+pub(crate) const MAX_QUANTIZED_BITS: u32 = f64::MANTISSA_DIGITS;
+
+// Index of the step nearest `v`, out of `max_steps + 1` spread evenly over `min..=min + span`.
+// Values outside the range clamp to the nearest end, and NaN (also from a zero span) becomes 0.
+pub(crate) fn quantize(v: f64, min: f64, span: f64, max_steps: u64) -> u64 {
+    let t = (v - min) / span;
+    if t.is_nan() || t <= 0.0 {
+        return 0;
+    }
+    if t >= 1.0 {
+        return max_steps;
+    }
+    round(t * max_steps as f64).min(i128::from(max_steps)) as u64
+}
+// Value of the step at index `steps`, on the same scale as `quantize`
+pub(crate) fn dequantize(steps: u64, min: f64, span: f64, max_steps: u64) -> f64 {
+    min + span * (steps as f64 / max_steps as f64)
+}
+
+/// IEEE 754 half-precision float, packed in 2 bytes. Converting from `f32` rounds to the nearest
+/// representable value (about 3 significant digits), and values beyond ±65504 become infinite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct F16(u16);
+
+impl F16 {
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+    pub fn from_f32(v: f32) -> Self {
+        let bits = v.to_bits();
+        let sign = (bits >> 16) as u16 & 0x8000;
+        let exp = (bits >> 23 & 0xff) as i32;
+        let man = bits & 0x7f_ffff;
+
+        if exp == 0xff {
+            // Infinity, or a quiet NaN keeping the top of the payload
+            let nan = if man != 0 { 0x200 | (man >> 13) as u16 } else { 0 };
+            return Self(sign | 0x7c00 | nan);
+        }
+        let exp = exp - 127 + 15;
+        if exp >= 0x1f {
+            return Self(sign | 0x7c00);
+        }
+        // Rounds to nearest, ties to even. A carry out of the mantissa bumps the exponent, up to
+        // infinity.
+        let (half, rem, shift) = if exp > 0 {
+            ((exp as u32) << 10 | man >> 13, man & 0x1fff, 13)
+        } else if exp >= -10 {
+            // Subnormal, with the implicit bit made explicit
+            let man = man | 0x80_0000;
+            let shift = (14 - exp) as u32;
+            (man >> shift, man & ((1 << shift) - 1), shift)
+        } else {
+            return Self(sign);
+        };
+        let midpoint = 1 << (shift - 1);
+        let round_up = rem > midpoint || (rem == midpoint && half & 1 == 1);
+        Self(sign | (half + round_up as u32) as u16)
+    }
+    pub fn to_f32(self) -> f32 {
+        let sign = u32::from(self.0 & 0x8000) << 16;
+        let exp = u32::from(self.0 >> 10 & 0x1f);
+        let man = u32::from(self.0 & 0x3ff);
+
+        match exp {
+            0 => {
+                // Zero or subnormal: man * 2^-24
+                let v = man as f32 * f32::from_bits(0x3380_0000);
+                f32::from_bits(sign | v.to_bits())
+            }
+            0x1f => f32::from_bits(sign | 0x7f80_0000 | man << 13),
+            _ => f32::from_bits(sign | (exp + 127 - 15) << 23 | man << 13),
+        }
+    }
+}
+impl From<f32> for F16 {
+    fn from(v: f32) -> Self {
+        Self::from_f32(v)
+    }
+}
+impl From<F16> for f32 {
+    fn from(v: F16) -> f32 {
+        v.to_f32()
+    }
+}
+impl Display for F16 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.to_f32().fmt(f)
+    }
+}
+
+impl EndianPackable for F16 {
+    fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+        stream.write_u16::<E>(self.0)
+    }
+    fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+        Ok(Self(rdr.read_u16::<E>()?))
+    }
+}
+impl Packable for F16 {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.pack_into_endian::<BigEndian>(stream)
+    }
+}
+impl Unpackable for F16 {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Self::unpack_from_endian::<BigEndian>(rdr)
+    }
+}
+impl PackedSize for F16 {
+    const SIZE: Option<usize> = Some(2);
+
+    fn packed_len(&self) -> usize {
+        2
+    }
+}
+
+/// Fixed-point number stored in the integer `I`, with `FRAC` fractional bits. Packs like `I`, and
+/// converting from a float is accurate to within half of `2^-FRAC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed<I, const FRAC: u32>(I);
+
+impl<I, const FRAC: u32> Fixed<I, FRAC> {
+    const SCALE: f64 = {
+        assert!(FRAC < 128, "too many fractional bits");
+        f64::from_bits((1023 + FRAC as u64) << 52)
+    };
+
+    pub const fn from_raw(raw: I) -> Self {
+        Self(raw)
+    }
+    pub fn raw(self) -> I {
+        self.0
+    }
+}
+impl<I: Copy + Into<i128> + TryFrom<i128>, const FRAC: u32> Fixed<I, FRAC> {
+    /// Rounds `v` to the nearest multiple of `2^-FRAC`, failing if the result doesn't fit in `I`
+    pub fn from_f64(v: f64) -> Result<Self, PackError> {
+        const LIMIT: f64 = i128::MAX as f64;
+
+        if v.is_nan() {
+            return Err(NOT_A_NUMBER);
+        }
+        let scaled = v * Self::SCALE;
+        if !(-LIMIT < scaled && scaled < LIMIT) {
+            return Err(OUT_OF_RANGE);
+        }
+        I::try_from(round(scaled)).map(Self).map_err(|_| OUT_OF_RANGE)
+    }
+    pub fn to_f64(self) -> f64 {
+        self.0.into() as f64 / Self::SCALE
+    }
+}
+impl<I: Copy + Into<i128> + TryFrom<i128>, const FRAC: u32> TryFrom<f64> for Fixed<I, FRAC> {
+    type Error = PackError;
+
+    fn try_from(v: f64) -> Result<Self, PackError> {
+        Self::from_f64(v)
+    }
+}
+impl<I: Copy + Into<i128> + TryFrom<i128>, const FRAC: u32> From<Fixed<I, FRAC>> for f64 {
+    fn from(v: Fixed<I, FRAC>) -> f64 {
+        v.to_f64()
+    }
+}
+impl<I: Copy + Into<i128> + TryFrom<i128>, const FRAC: u32> Display for Fixed<I, FRAC> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.to_f64().fmt(f)
+    }
+}
+
+impl<I: EndianPackable, const FRAC: u32> EndianPackable for Fixed<I, FRAC> {
+    fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+        self.0.pack_into_endian::<E>(stream)
+    }
+    fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+        Ok(Self(I::unpack_from_endian::<E>(rdr)?))
+    }
+}
+impl<I: Packable, const FRAC: u32> Packable for Fixed<I, FRAC> {
+    type Error = I::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into(stream)
+    }
+}
+impl<I: Unpackable, const FRAC: u32> Unpackable for Fixed<I, FRAC> {
+    type Error = I::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(I::unpack_from(rdr)?))
+    }
+}
+impl<I: PackedSize, const FRAC: u32> PackedSize for Fixed<I, FRAC> {
+    const SIZE: Option<usize> = I::SIZE;
+
+    fn packed_len(&self) -> usize {
+        self.0.packed_len()
+    }
+}
+
+/// Float in the range `MIN..=MAX`, packed as one of `2^BITS` evenly spaced steps in the fewest
+/// whole bytes. Accurate to within half a step, `(MAX - MIN) / (2^BITS - 1) / 2`. `BITS` must
+/// be 1 to 53, so that every step is exact in an `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Quantized<const MIN: i64, const MAX: i64, const BITS: u32> {
+    steps: u64,
+}
+
+impl<const MIN: i64, const MAX: i64, const BITS: u32> Quantized<MIN, MAX, BITS> {
+    const MAX_STEPS: u64 = {
+        assert!(MIN < MAX, "empty quantized range");
+        assert!(BITS >= 1 && BITS <= MAX_QUANTIZED_BITS, "quantized values must be 1 to 53 bits");
+        u64::MAX >> (64 - BITS)
+    };
+    const BYTES: usize = (BITS as usize).div_ceil(8);
+    const SPAN: f64 = (MAX as i128 - MIN as i128) as f64;
+
+    /// Fails if `v` is outside `MIN..=MAX`
+    pub fn new(v: f64) -> Result<Self, PackError> {
+        if v.is_nan() {
+            return Err(NOT_A_NUMBER);
+        }
+        if v < MIN as f64 || v > MAX as f64 {
+            return Err(OUT_OF_RANGE);
+        }
+        Ok(Self::clamped(v))
+    }
+    /// Clamps `v` to `MIN..=MAX`. NaN becomes `MIN`.
+    pub fn clamped(v: f64) -> Self {
+        Self { steps: quantize(v, MIN as f64, Self::SPAN, Self::MAX_STEPS) }
+    }
+    pub fn get(self) -> f64 {
+        dequantize(self.steps, MIN as f64, Self::SPAN, Self::MAX_STEPS)
+    }
+    /// Index of the step the value was rounded to, as packed
+    pub fn steps(self) -> u64 {
+        self.steps
+    }
+    fn from_steps(steps: u64) -> Result<Self, PackError> {
+        if steps > Self::MAX_STEPS {
+            return Err(OUT_OF_RANGE);
+        }
+        Ok(Self { steps })
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> From<Quantized<MIN, MAX, BITS>> for f64 {
+    fn from(v: Quantized<MIN, MAX, BITS>) -> f64 {
+        v.get()
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> Display for Quantized<MIN, MAX, BITS> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<const MIN: i64, const MAX: i64, const BITS: u32> EndianPackable for Quantized<MIN, MAX, BITS> {
+    fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+        stream.write_uint::<E>(self.steps, Self::BYTES)
+    }
+    fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+        Self::from_steps(rdr.read_uint::<E>(Self::BYTES)?)
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> Packable for Quantized<MIN, MAX, BITS> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.pack_into_endian::<BigEndian>(stream)
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> Unpackable for Quantized<MIN, MAX, BITS> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Self::unpack_from_endian::<BigEndian>(rdr)
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> PackedSize for Quantized<MIN, MAX, BITS> {
+    const SIZE: Option<usize> = Some(Self::BYTES);
+
+    fn packed_len(&self) -> usize {
+        Self::BYTES
+    }
+}
+// Bit packed in exactly `BITS` bits
+impl<const MIN: i64, const MAX: i64, const BITS: u32> BitPackable for Quantized<MIN, MAX, BITS> {
+    fn bit_pack_into(&self, wtr: &mut BitWriter<impl Write>) -> Result<(), PackError> {
+        wtr.write_bits(self.steps, BITS)
+    }
+}
+impl<const MIN: i64, const MAX: i64, const BITS: u32> BitUnpackable for Quantized<MIN, MAX, BITS> {
+    fn bit_unpack_from(rdr: &mut BitReader<impl Read>) -> Result<Self, PackError> {
+        Self::from_steps(rdr.read_bits(BITS)?)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+    use proptest::prelude::*;
+
+    type Angle = Quantized<-180, 180, 12>;
+
+    #[test]
+    fn test_f16() {
+        assert_eq!(F16::from_f32(1.0).to_bits(), 0x3c00);
+        assert_eq!(F16::from_f32(-2.0).to_bits(), 0xc000);
+        assert_eq!(F16::from_f32(65504.0).to_bits(), 0x7bff);
+        assert_eq!(F16::from_f32(65520.0).to_bits(), 0x7c00);
+        assert_eq!(F16::from_f32(f32::NEG_INFINITY).to_bits(), 0xfc00);
+        assert_eq!(F16::from_f32(2f32.powi(-24)).to_bits(), 0x0001);
+        assert_eq!(F16::from_f32(2f32.powi(-25)).to_bits(), 0x0000);
+        assert_eq!(F16::from_f32(1.0 + 2f32.powi(-11)).to_bits(), 0x3c00);
+        assert_eq!(F16::from_f32(1.0 + 3.0 * 2f32.powi(-11)).to_bits(), 0x3c02);
+        assert!(F16::from_f32(f32::NAN).to_f32().is_nan());
+        assert_eq!(F16::from_bits(0x0400).to_f32(), 2f32.powi(-14));
+        assert_eq!(F16::from_bits(0x8001).to_f32(), -(2f32.powi(-24)));
+    }
+
+    #[test]
+    fn test_pack_float_types() {
+        let v1 = F16::from_f32(1.5);
+        let v2: Fixed<i16, 8> = Fixed::from_f64(-1.5).unwrap();
+        let v3 = Angle::new(180.0).unwrap();
+
+        assert_eq!(v1.pack().unwrap(), vec![0x3e, 0x00]);
+        assert_eq!(Le(v1).pack().unwrap(), vec![0x00, 0x3e]);
+        assert_eq!(v2.pack().unwrap(), vec![0xfe, 0x80]);
+        assert_eq!(Le(v2).pack().unwrap(), vec![0x80, 0xfe]);
+        assert_eq!(v3.pack().unwrap(), vec![0x0f, 0xff]);
+        assert_eq!(Le(v3).pack().unwrap(), vec![0xff, 0x0f]);
+        assert_eq!((v1, v2, v3).packed_len(), 6);
+        assert_eq!(Quantized::<0, 1, 1>::SIZE, Some(1));
+        assert_eq!(Quantized::<0, 1, 17>::SIZE, Some(3));
+    }
+
+    #[test]
+    fn test_float_type_errors() {
+        type Byte = Fixed<u8, 4>;
+
+        assert_eq!(Byte::from_f64(16.0), Err(PackError::InvalidValue("value is out of range")));
+        assert_eq!(Byte::from_f64(-0.5), Err(PackError::InvalidValue("value is out of range")));
+        assert_eq!(Byte::from_f64(f64::NAN), Err(PackError::InvalidValue("value is not a number")));
+        assert_eq!(Byte::from_f64(15.9).map(Byte::raw), Ok(0xfe));
+        assert_eq!(Angle::new(180.5), Err(PackError::InvalidValue("value is out of range")));
+        assert_eq!(Angle::new(f64::NAN), Err(PackError::InvalidValue("value is not a number")));
+        assert_eq!(Angle::clamped(1000.0).get(), 180.0);
+        assert_eq!(Angle::clamped(f64::NEG_INFINITY).get(), -180.0);
+        assert_eq!(unpack::<Angle>(&[0x10, 0x00]).err(), Some(PackError::InvalidValue("value is out of range")));
+        assert_eq!(unpack::<Angle>(&[0x0f]).err(), Some(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_bit_pack_quantized() {
+        type Nibble = Quantized<0, 10, 4>;
+
+        let mut wtr = BitWriter::new(Vec::new());
+        Nibble::new(2.5).unwrap().bit_pack_into(&mut wtr).unwrap();
+        wtr.write_quantized(2.5, 0.0, 10.0, 4).unwrap();
+        Angle::new(90.0).unwrap().bit_pack_into(&mut wtr).unwrap();
+        let i = wtr.finish().unwrap();
+
+        let mut rdr = BitReader::new(&i[..]);
+        let t1 = Nibble::bit_unpack_from(&mut rdr).unwrap();
+        let t2 = rdr.read_quantized(0.0, 10.0, 4).unwrap();
+        let t3 = Angle::bit_unpack_from(&mut rdr).unwrap();
+
+        assert_eq!(i, vec![0x44, 0xbf, 0xf0]);
+        assert_eq!(t1.steps(), 4);
+        assert_eq!(t2, t1.get());
+        assert_eq!(t3, Angle::new(90.0).unwrap());
+        assert_eq!(Quantized::<0, 1, 53>::clamped(1.0).bit_pack().unwrap(), [vec![0xff; 6], vec![0xf8]].concat());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_f16_bits_roundtrip(bits in any::<u16>()) {
+            let v = F16::from_bits(bits);
+            let (t, _): (F16, _) = unpack(&v.pack().unwrap()).unwrap();
+
+            prop_assert_eq!(t, v);
+            if !v.to_f32().is_nan() {
+                prop_assert_eq!(F16::from_f32(v.to_f32()), v);
+            }
+        }
+
+        #[test]
+        fn prop_f16_precision(v in -65504f32..65504f32) {
+            let t = F16::from_f32(v).to_f32();
+
+            // Half a unit in the last place, with subnormals spaced 2^-24 apart
+            let tolerance = (v.abs() * 2f32.powi(-11)).max(2f32.powi(-25));
+            prop_assert!((t - v).abs() <= tolerance, "{} became {}", v, t);
+        }
+
+        #[test]
+        fn prop_fixed_precision(v in -128f64..127.99) {
+            let f: Fixed<i32, 24> = Fixed::from_f64(v).unwrap();
+            let (t, _): (Le<Fixed<i32, 24>>, _) = unpack(&Le(f).pack().unwrap()).unwrap();
+
+            prop_assert_eq!(t.0, f);
+            prop_assert!((f.to_f64() - v).abs() <= 0.5 / (1 << 24) as f64);
+        }
+
+        #[test]
+        fn prop_fixed_range(raw in any::<i16>()) {
+            let f: Fixed<i16, 5> = Fixed::from_raw(raw);
+
+            prop_assert_eq!(Fixed::<i16, 5>::from_f64(f.to_f64()), Ok(f));
+        }
+
+        #[test]
+        fn prop_quantized_precision(v in -180f64..=180.0) {
+            let q = Angle::new(v).unwrap();
+            let (t, _): (Angle, _) = unpack(&q.pack().unwrap()).unwrap();
+
+            prop_assert_eq!(t, q);
+            prop_assert!((q.get() - v).abs() <= 360.0 / 4095.0 / 2.0 + 1e-12);
+        }
+
+        #[test]
+        fn prop_quantized_clamps(v in any::<f64>()) {
+            let q = Quantized::<-1, 1, 7>::clamped(v);
+
+            prop_assert!((-1.0..=1.0).contains(&q.get()));
+            prop_assert_eq!(Quantized::<-1, 1, 7>::new(v).is_ok(), (-1.0..=1.0).contains(&v));
+        }
+
+        #[test]
+        fn prop_quantized_bits_match_bit_writer(v in -180f64..=180.0) {
+            let mut wtr = BitWriter::new(Vec::new());
+            wtr.write_quantized(v, -180.0, 180.0, 12).unwrap();
+
+            prop_assert_eq!(wtr.finish().unwrap(), Angle::new(v).unwrap().bit_pack().unwrap());
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Byte sink and source traits used by `Packable` and `Unpackable`. They are implemented for byte
+//! slices, `&mut` references and (with `alloc`) `Vec<u8>`. The `std` feature adds the common
+//! `std::io` readers and writers, and `StdIo` for the rest.
+
+use byteorder::ByteOrder;
+
+use crate::PackError;
+
+pub trait Write {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError>;
+}
+pub trait Read {
+    /// Fills `buf` completely, failing with `PackError::SizeError` if the input ends first
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError>;
+    // How deeply `Box`, `Rc` and `Arc` values are nested at this point of the input. Only the
+    // reader handed to a pointer's contents tracks it; wrappers forward it from the reader they
+    // wrap.
+    #[doc(hidden)]
+    fn nesting(&mut self) -> Option<&mut Nesting> {
+        None
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub struct Nesting {
+    pub(crate) depth: usize,
+    pub(crate) max: usize,
+}
+
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        (**self).write_all(buf)
+    }
+}
+// Fills the slice from the front, moving it past the bytes written
+impl Write for &mut [u8] {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        if buf.len() > self.len() {
+            return Err(PackError::BufferTooSmall { needed: buf.len(), available: self.len() });
+        }
+        let (head, rest) = core::mem::take(self).split_at_mut(buf.len());
+        head.copy_from_slice(buf);
+        *self = rest;
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl Write for alloc::vec::Vec<u8> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+impl<R: Read + ?Sized> Read for &mut R {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        (**self).read_exact(buf)
+    }
+    fn nesting(&mut self) -> Option<&mut Nesting> {
+        (**self).nesting()
+    }
+}
+impl Read for &[u8] {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        if buf.len() > self.len() {
+            return Err(PackError::SizeError);
+        }
+        let (head, rest) = self.split_at(buf.len());
+        buf.copy_from_slice(head);
+        *self = rest;
+        Ok(())
+    }
+}
+
+/// Wraps any `std::io::Read` or `std::io::Write` for use with this module's traits. Common
+/// readers and writers such as `Cursor`, `File` and `TcpStream` implement them directly.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct StdIo<T>(pub T);
+#[cfg(feature = "std")]
+impl<T: std::io::Write> Write for StdIo<T> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        Ok(self.0.write_all(buf)?)
+    }
+}
+#[cfg(feature = "std")]
+impl<T: std::io::Read> Read for StdIo<T> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        Ok(self.0.read_exact(buf)?)
+    }
+}
+
+// The common std readers and writers, so they don't need wrapping in `StdIo`
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::boxed::Box;
+    use std::fs::File;
+    use std::io::{BufReader, BufWriter, Cursor, LineWriter, Stderr, StderrLock, Stdin, StdinLock, Stdout, StdoutLock};
+    use std::net::TcpStream;
+    use std::vec::Vec;
+
+    use super::{Read, Write};
+    use crate::PackError;
+
+    macro_rules! std_write {
+        ($([$($g:tt)*] $t:ty),*) => {$(
+            impl<$($g)*> Write for $t {
+                fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+                    Ok(std::io::Write::write_all(self, buf)?)
+                }
+            }
+        )*};
+    }
+    std_write!(
+        ['a] Cursor<&'a mut [u8]>,
+        ['a] Cursor<&'a mut Vec<u8>>,
+        [] Cursor<Vec<u8>>,
+        [] Cursor<Box<[u8]>>,
+        [W: std::io::Write] BufWriter<W>,
+        [W: std::io::Write] LineWriter<W>,
+        [] File,
+        ['a] &'a File,
+        [] TcpStream,
+        ['a] &'a TcpStream,
+        [] Stdout,
+        ['a] StdoutLock<'a>,
+        [] Stderr,
+        ['a] StderrLock<'a>
+    );
+
+    macro_rules! std_read {
+        ($([$($g:tt)*] $t:ty),*) => {$(
+            impl<$($g)*> Read for $t {
+                fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+                    Ok(std::io::Read::read_exact(self, buf)?)
+                }
+            }
+        )*};
+    }
+    std_read!(
+        [T: AsRef<[u8]>] Cursor<T>,
+        [R: std::io::Read] BufReader<R>,
+        [] File,
+        ['a] &'a File,
+        [] TcpStream,
+        ['a] &'a TcpStream,
+        [] Stdin,
+        ['a] StdinLock<'a>
+    );
+}
+
+// Copies into a fixed buffer while it has room, but keeps counting past the end so a value that
+// doesn't fit can still report its full size
+pub(crate) struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+impl<'a> SliceWriter<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+    fn put(&mut self, data: &[u8]) {
+        if let Some(dst) = self.buf.get_mut(self.len..self.len + data.len()) {
+            dst.copy_from_slice(data);
+        }
+        self.len += data.len();
+    }
+    pub(crate) fn finish(self) -> Result<usize, PackError> {
+        if self.len > self.buf.len() {
+            return Err(PackError::BufferTooSmall { needed: self.len, available: self.buf.len() });
+        }
+        Ok(self.len)
+    }
+}
+impl Write for SliceWriter<'_> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+        self.put(buf);
+        Ok(())
+    }
+}
+
+macro_rules! write_ext {
+    ($($write:ident($t:ty, $n:expr);)*) => {
+        pub(crate) trait WriteBytesExt: Write {
+            fn write_u8(&mut self, n: u8) -> Result<(), PackError> {
+                self.write_all(&[n])
+            }
+            fn write_i8(&mut self, n: i8) -> Result<(), PackError> {
+                self.write_all(&[n as u8])
+            }
+            fn write_uint<E: ByteOrder>(&mut self, n: u64, nbytes: usize) -> Result<(), PackError> {
+                let mut buf = [0; 8];
+                E::write_uint(&mut buf, n, nbytes);
+                self.write_all(&buf[..nbytes])
+            }
+            fn write_int<E: ByteOrder>(&mut self, n: i64, nbytes: usize) -> Result<(), PackError> {
+                let mut buf = [0; 8];
+                E::write_int(&mut buf, n, nbytes);
+                self.write_all(&buf[..nbytes])
+            }
+            $(
+                fn $write<E: ByteOrder>(&mut self, n: $t) -> Result<(), PackError> {
+                    let mut buf = [0; $n];
+                    E::$write(&mut buf, n);
+                    self.write_all(&buf)
+                }
+            )*
+        }
+    };
+}
+write_ext! {
+    write_u16(u16, 2);
+    write_i16(i16, 2);
+    write_u32(u32, 4);
+    write_i32(i32, 4);
+    write_u64(u64, 8);
+    write_i64(i64, 8);
+    write_u128(u128, 16);
+    write_i128(i128, 16);
+    write_f32(f32, 4);
+    write_f64(f64, 8);
+}
+impl<W: Write + ?Sized> WriteBytesExt for W {}
+
+macro_rules! read_ext {
+    ($($read:ident($t:ty, $n:expr);)*) => {
+        pub(crate) trait ReadBytesExt: Read {
+            fn read_u8(&mut self) -> Result<u8, PackError> {
+                let mut buf = [0];
+                self.read_exact(&mut buf)?;
+                Ok(buf[0])
+            }
+            fn read_i8(&mut self) -> Result<i8, PackError> {
+                Ok(self.read_u8()? as i8)
+            }
+            fn read_uint<E: ByteOrder>(&mut self, nbytes: usize) -> Result<u64, PackError> {
+                let mut buf = [0; 8];
+                self.read_exact(&mut buf[..nbytes])?;
+                Ok(E::read_uint(&buf, nbytes))
+            }
+            fn read_int<E: ByteOrder>(&mut self, nbytes: usize) -> Result<i64, PackError> {
+                let mut buf = [0; 8];
+                self.read_exact(&mut buf[..nbytes])?;
+                Ok(E::read_int(&buf, nbytes))
+            }
+            $(
+                fn $read<E: ByteOrder>(&mut self) -> Result<$t, PackError> {
+                    let mut buf = [0; $n];
+                    self.read_exact(&mut buf)?;
+                    Ok(E::$read(&buf))
+                }
+            )*
+        }
+    };
+}
+read_ext! {
+    read_u16(u16, 2);
+    read_i16(i16, 2);
+    read_u32(u32, 4);
+    read_i32(i32, 4);
+    read_u64(u64, 8);
+    read_i64(i64, 8);
+    read_u128(u128, 16);
+    read_i128(i128, 16);
+    read_f32(f32, 4);
+    read_f64(f64, 8);
+}
+impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+// Only uses the core paths, so these also run without `alloc`
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use core::net::Ipv4Addr;
+
+    type Mixed = (u8, Le<u32>, VarU64, QuicVarInt, Option<i16>, Ipv4Addr, U24, F16);
+
+    #[test]
+    fn test_slice_read_write() {
+        let mut buf = [0; 6];
+        let mut wtr = &mut buf[..];
+
+        0x0102u16.pack_into(&mut wtr).unwrap();
+        Le(0x0304u16).pack_into(&mut wtr).unwrap();
+        assert_eq!(wtr.len(), 2);
+        assert_eq!(buf, [1, 2, 4, 3, 0, 0]);
+
+        let mut rdr = &buf[..];
+        assert_eq!(unpack_from::<[u8; 4]>(&mut rdr), Ok([1, 2, 4, 3]));
+        assert_eq!(unpack_from::<u32>(&mut rdr), Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_roundtrip_pack_to_slice() {
+        let v: Mixed = (1, Le(2), VarU64(300), QuicVarInt(15293), Some(-1), Ipv4Addr::LOCALHOST, U24::MAX, F16::from_f32(0.5));
+
+        let mut buf = [0; 32];
+        let len = v.pack_to_slice(&mut buf).unwrap();
+        let (t, tb): (Mixed, _) = unpack(&buf[..len + 1]).unwrap();
+
+        assert_eq!(len, 1 + 4 + 2 + 2 + 3 + 4 + 3 + 2);
+        assert_eq!(&buf[..9], &[1, 2, 0, 0, 0, 0xac, 0x02, 0x7b, 0xbd]);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[0]);
+        assert_eq!(v.pack_to_slice(&mut buf[..20]), Err(PackError::BufferTooSmall { needed: 21, available: 20 }));
+    }
+
+    #[test]
+    fn test_bits_to_slice() {
+        let mut buf = [0; 2];
+        let mut wtr = BitWriter::new(&mut buf[..]);
+        Bits::<u8, 3>(5).bit_pack_into(&mut wtr).unwrap();
+        Bits::<i16, 9>(-2).bit_pack_into(&mut wtr).unwrap();
+        assert_eq!(wtr.finish().unwrap().len(), 0);
+
+        let mut rdr = BitReader::new(&buf[..]);
+        assert_eq!(buf, [0b1011_1111, 0b1110_0000]);
+        assert_eq!(Bits::<u8, 3>::bit_unpack_from(&mut rdr), Ok(Bits(5)));
+        assert_eq!(Bits::<i16, 9>::bit_unpack_from(&mut rdr), Ok(Bits(-2)));
+    }
+
+    // Byte counter that only implements this module's `Write`
+    struct Counter(usize);
+    impl io::Write for Counter {
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), PackError> {
+            self.0 += buf.len();
+            Ok(())
+        }
+    }
+
+    // Reader that tracks pointer nesting, like the one `Box` contents are read through
+    struct Tracked(io::Nesting);
+    impl io::Read for Tracked {
+        fn read_exact(&mut self, _: &mut [u8]) -> Result<(), PackError> {
+            Err(PackError::SizeError)
+        }
+        fn nesting(&mut self) -> Option<&mut io::Nesting> {
+            Some(&mut self.0)
+        }
+    }
+
+    // Passes readers and writers on by value and by reborrow, as generic code does
+    fn copy_generic<R: io::Read, W: io::Write>(rdr: R, wtr: &mut W) -> Result<u16, PackError> {
+        let mut rdr = rdr;
+        let v = u16::unpack_from(&mut rdr)?;
+        let mut bits = BitWriter::new(&mut *wtr);
+        bits.write_bits(u64::from(v >> 12), 4)?;
+        bits.finish()?;
+        v.pack_into(&mut &mut *wtr)?;
+        Ok(v)
+    }
+
+    #[test]
+    fn test_generic_read_write() {
+        let mut input = &[0x12, 0x34, 0xff][..];
+        let mut out = [0; 3];
+        let mut small = [0; 2];
+        let mut counter = Counter(0);
+        let mut tracked = Tracked(io::Nesting { depth: 2, max: 5 });
+
+        let v1 = copy_generic(&mut input, &mut &mut out[..]);
+        let v2 = copy_generic(&[0x56, 0x78][..], &mut counter);
+        let v3 = copy_generic(&[0x9a, 0xbc][..], &mut &mut small[..]);
+        let v4 = copy_generic(&mut &mut tracked, &mut counter);
+
+        assert_eq!(v1, Ok(0x1234));
+        assert_eq!(out, [0x10, 0x12, 0x34]);
+        assert_eq!(input, &[0xff]);
+        assert_eq!(v2, Ok(0x5678));
+        assert_eq!(counter.0, 3);
+        assert_eq!(v3, Err(PackError::BufferTooSmall { needed: 2, available: 1 }));
+        assert_eq!(small, [0x90, 0]);
+        assert_eq!(v4, Err(PackError::SizeError));
+        assert_eq!(io::Read::nesting(&mut &mut &mut tracked).map(|n| n.depth), Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_std_read_write() {
+        let mut c = std::io::Cursor::new(std::vec::Vec::new());
+        0x0102u16.pack_into(&mut c).unwrap();
+        io::StdIo(&mut c).pack(&3u8).unwrap();
+        c.set_position(0);
+
+        assert_eq!(unpack_from::<[u8; 3]>(&mut c), Ok([1, 2, 3]));
+        assert_eq!(unpack_from::<u8>(&mut io::StdIo(&mut c)), Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_unpack_borrowed_core() {
+        let (v1, b1): (LenPrefixed<u8, &str>, _) = unpack_borrowed(&[2, b'h', b'i', 7, 8, 9]).unwrap();
+        let (v2, b2): (&[u8; 2], _) = unpack_borrowed(b1).unwrap();
+
+        assert_eq!(*v1, "hi");
+        assert_eq!(v2, &[7, 8]);
+        assert_eq!(b2, &[9]);
+    }
+}
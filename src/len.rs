@@ -0,0 +1,210 @@
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::io::{Read, Write};
+use crate::size::len_sum;
+
+use crate::{EndianPackable, Le, PackError, Packable, PackedSize, Unpackable};
+
+// Upper bound on capacity reserved up front from an untrusted length prefix
+#[cfg(feature = "alloc")]
+pub(crate) const MAX_PREALLOC: usize = 4096;
+
+/// Unsigned integer types that can encode the length of a collection
+pub trait LengthPrefix: Packable<Error = PackError> + Unpackable<Error = PackError> + Copy {
+    fn from_len(len: usize) -> Result<Self, PackError>;
+    fn to_len(self) -> Result<usize, PackError>;
+}
+
+macro_rules! length_prefix {
+    ($($t:ty),*) => {$(
+        impl LengthPrefix for $t {
+            fn from_len(len: usize) -> Result<Self, PackError> {
+                <$t>::try_from(len).map_err(|_| PackError::LengthLimit {
+                    len: len as u64,
+                    max: u64::try_from(<$t>::MAX).unwrap_or(u64::MAX),
+                })
+            }
+            fn to_len(self) -> Result<usize, PackError> {
+                usize::try_from(self).map_err(|_| PackError::LengthLimit {
+                    len: u64::try_from(self).unwrap_or(u64::MAX),
+                    max: usize::MAX as u64,
+                })
+            }
+        }
+    )*};
+}
+length_prefix!(u8, u16, u32, u64, u128);
+
+impl<T: LengthPrefix + EndianPackable> LengthPrefix for Le<T> {
+    fn from_len(len: usize) -> Result<Self, PackError> {
+        T::from_len(len).map(Le)
+    }
+    fn to_len(self) -> Result<usize, PackError> {
+        self.0.to_len()
+    }
+}
+
+/// Packs a collection as its element count, encoded as `L`, followed by the elements
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct LenPrefixed<L, C> {
+    inner: C,
+    _len: PhantomData<L>,
+}
+impl<L, C> LenPrefixed<L, C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, _len: PhantomData }
+    }
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+impl<L, C> From<C> for LenPrefixed<L, C> {
+    fn from(inner: C) -> Self {
+        Self::new(inner)
+    }
+}
+impl<L, C> Deref for LenPrefixed<L, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.inner
+    }
+}
+impl<L, C> DerefMut for LenPrefixed<L, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+}
+
+pub(crate) fn pack_len<L: LengthPrefix>(len: usize, stream: &mut impl Write) -> Result<(), PackError> {
+    L::from_len(len)?.pack_into(stream)
+}
+pub(crate) fn unpack_len<L: LengthPrefix>(rdr: &mut impl Read) -> Result<usize, PackError> {
+    L::unpack_from(rdr)?.to_len()
+}
+
+impl<L: LengthPrefix, T: Packable> Packable for LenPrefixed<L, &[T]> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_len::<L>(self.inner.len(), stream)?;
+        for v in self.inner {
+            v.pack_into(stream)?;
+        }
+
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix, T: Packable> Packable for LenPrefixed<L, Vec<T>> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        LenPrefixed::<L, _>::new(self.inner.as_slice()).pack_into(stream)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix, T: Unpackable> Unpackable for LenPrefixed<L, Vec<T>> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        let mut v = Vec::with_capacity(len.min(MAX_PREALLOC));
+
+        for _ in 0..len {
+            v.push(T::unpack_from(rdr)?);
+        }
+
+        Ok(Self::new(v))
+    }
+}
+
+// A length that doesn't fit in `L` can't be packed at all, so it gets the largest size
+pub(crate) fn len_size<L: LengthPrefix + PackedSize>(len: usize) -> usize {
+    L::from_len(len).map_or(usize::MAX, |l| l.packed_len())
+}
+impl<L: LengthPrefix + PackedSize, T: PackedSize> PackedSize for LenPrefixed<L, &[T]> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        len_size::<L>(self.len()).saturating_add(len_sum(self.iter()))
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix + PackedSize, T: PackedSize> PackedSize for LenPrefixed<L, Vec<T>> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        len_size::<L>(self.len()).saturating_add(len_sum(self.iter()))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_pack_len_prefixed() {
+        let v1: LenPrefixed<u8, Vec<u16>> = vec![0x0123, 0x4567].into();
+        let v2: LenPrefixed<u32, Vec<u8>> = vec![].into();
+        let v3: LenPrefixed<u16, &[u8]> = LenPrefixed::new(&[1, 2, 3]);
+
+        assert_eq!(v1.pack().unwrap(), vec![2, 0x01, 0x23, 0x45, 0x67]);
+        assert_eq!(v2.pack().unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(v3.pack().unwrap(), vec![0, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_roundtrip_len_prefixed_le() {
+        let v: LenPrefixed<Le<u16>, Vec<u8>> = vec![0xaa; 3].into();
+
+        let i = v.pack().unwrap();
+        let (t, tb): (LenPrefixed<Le<u16>, Vec<u8>>, _) = unpack(&i).unwrap();
+
+        assert_eq!(i, vec![3, 0, 0xaa, 0xaa, 0xaa]);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+    }
+
+    #[test]
+    fn test_pack_len_prefixed_overflow() {
+        let v: LenPrefixed<u8, Vec<u8>> = vec![0; 256].into();
+
+        assert_eq!(v.pack(), Err(PackError::LengthLimit { len: 256, max: 255 }));
+    }
+
+    #[test]
+    fn test_unpack_len_prefixed() {
+        let i1 = [];
+        let i2 = [2, 0x01, 0x23, 0x45];
+        let i3 = [2, 0x01, 0x23, 0x45, 0x67, 0xff];
+        let i4 = [0xff];
+
+        let v1: Result<(LenPrefixed<u8, Vec<u16>>, _), PackError> = unpack(&i1);
+        let v2: Result<(LenPrefixed<u8, Vec<u16>>, _), PackError> = unpack(&i2);
+        let (v3, b3): (LenPrefixed<u8, Vec<u16>>, _) = unpack(&i3).unwrap();
+        let v4: Result<(LenPrefixed<u8, Vec<u16>>, _), PackError> = unpack(&i4);
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Err(PackError::SizeError));
+        assert_eq!(*v3, vec![0x0123, 0x4567]);
+        assert_eq!(b3, &[0xff]);
+        assert_eq!(v4, Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_roundtrip_len_prefixed() {
+        let v: LenPrefixed<u64, Vec<[i8; 2]>> = vec![[1, -1], [2, -2], [3, -3]].into();
+
+        let i = v.pack().unwrap();
+        let (t, tb): (LenPrefixed<u64, Vec<[i8; 2]>>, _) = unpack(&i).unwrap();
+
+        assert_eq!(i.len(), 14);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+    }
+}
@@ -1,51 +1,216 @@
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::{convert::TryInto, fmt::{self, Display, Formatter}};
-use std::io::{self, Cursor, Read, Write};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+// Test modules glob-import the crate root, which stands in for the std prelude without std
+#[cfg(all(test, feature = "alloc"))]
+use alloc::{format, string::{String, ToString}, vec};
+#[cfg(feature = "std")]
+use std::io as std_io;
+
+use crate::io::{Read, ReadBytesExt, SliceWriter, Write, WriteBytesExt};
+
+pub mod io;
+
+#[cfg(feature = "async")]
+mod async_io;
+mod bits;
+mod borrowed;
+#[cfg(feature = "codec")]
+mod codec;
+#[cfg(feature = "alloc")]
+mod collections;
+mod endian;
+mod float;
+mod len;
+mod net;
+mod num;
+mod odd_int;
+mod partial;
+#[cfg(feature = "alloc")]
+mod ptr;
+mod size;
+mod string;
+mod time;
+mod varint;
+
+#[cfg(feature = "async")]
+pub use async_io::{AsyncPackable, AsyncReadPackExt, AsyncUnpackable, AsyncWritePackExt};
+pub use bits::{BitPackable, BitReader, BitUnpackable, BitWriter, Bits, Packed};
+pub use borrowed::{unpack_borrowed, UnpackBorrowed};
+pub use byteorder::{BigEndian, ByteOrder, LittleEndian, NetworkEndian};
+#[cfg(feature = "codec")]
+pub use codec::NetpackCodec;
+#[cfg(feature = "alloc")]
+pub use collections::Sorted;
+pub use endian::{Be, EndianPackable, Le};
+pub use float::{Fixed, Quantized, F16};
+pub use len::{LenPrefixed, LengthPrefix};
+pub use net::MacAddr;
+pub use num::{Isize, Usize};
+pub use odd_int::{I24, I40, I48, I56, U24, U40, U48, U56};
+#[cfg(feature = "alloc")]
+pub use partial::StreamDecoder;
+pub use partial::{try_unpack, Status};
+#[cfg(feature = "alloc")]
+pub use ptr::{MaxDepth, DEFAULT_RECURSION_LIMIT};
+pub use size::PackedSize;
+#[cfg(feature = "alloc")]
+pub use string::FromBytes;
+pub use string::{AsBytes, FixedWidth, NulTerminated};
+pub use time::{Millis, Ntp64, SecsNanos, Timestamp};
+pub use varint::{QuicVarInt, VarI64, VarU64};
+
+#[cfg(feature = "derive")]
+pub use netpack_derive::{PackedSize, Packable, UnpackBorrowed, Unpackable};
+
+// Paths used by derived code
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::size::{size_add, size_mul, size_same};
+}
 
 pub trait Packable {
-    type Error;
+    /// Built-in impls use `PackError`. Custom error types only need `From<PackError>` (which
+    /// `io::Error` also has), so `?` works across any mix of field types.
+    type Error: From<PackError>;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error>;
+    #[cfg(feature = "alloc")]
     fn pack(&self) -> Result<Vec<u8>, Self::Error> {
         let mut wtr = Vec::new();
         self.pack_into(&mut wtr)?;
         Ok(wtr)
     }
+    /// Packs into the start of `buf` without allocating, returning the number of bytes written.
+    /// Fails with `PackError::BufferTooSmall` if the value doesn't fit.
+    fn pack_to_slice(&self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut wtr = SliceWriter::new(buf);
+        self.pack_into(&mut wtr)?;
+        Ok(wtr.finish()?)
+    }
 }
 pub trait Unpackable where Self: Sized {
-    type Error;
+    /// See `Packable::Error`
+    type Error: From<PackError>;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error>;
+    #[allow(clippy::needless_lifetimes)]
     fn unpack<'a>(buf: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
-        let mut rdr = Cursor::new(buf);
+        let mut rdr = buf;
         let val = Self::unpack_from(&mut rdr)?;
-        Ok((val, &buf[rdr.position() as usize..]))
+        Ok((val, rdr))
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum PackError {
+    /// The input ended before the value was complete
     SizeError,
+    /// An enum tag that doesn't match any variant
+    UnknownTag(u64),
+    /// Bytes that don't form a valid value of the type being unpacked
+    InvalidValue(&'static str),
+    /// A variable-length integer that is overlong or overflows its type
+    InvalidVarInt,
+    /// A length that exceeds what the encoding or the receiver allows
+    LengthLimit { len: u64, max: u64 },
+    /// The output buffer can't hold the packed value, which needs `needed` bytes
+    BufferTooSmall { needed: usize, available: usize },
+    /// An integer that doesn't fit in the width it is packed or unpacked as
+    Overflow,
+    /// Boxed values nested deeper than `DEFAULT_RECURSION_LIMIT`, or the limit set with `MaxDepth`
+    RecursionLimit { max: usize },
+    /// Any I/O error other than running out of input
+    #[cfg(feature = "std")]
+    Io(std_io::Error),
+    /// An error raised by user code, e.g. while validating an unpacked value
+    #[cfg(feature = "alloc")]
+    Custom(Box<dyn core::error::Error + Send + Sync>),
+}
+impl PackError {
+    #[cfg(feature = "alloc")]
+    pub fn custom(e: impl Into<Box<dyn core::error::Error + Send + Sync>>) -> Self {
+        Self::Custom(e.into())
+    }
+}
+impl PartialEq for PackError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::SizeError, Self::SizeError) => true,
+            (Self::UnknownTag(a), Self::UnknownTag(b)) => a == b,
+            (Self::InvalidValue(a), Self::InvalidValue(b)) => a == b,
+            (Self::InvalidVarInt, Self::InvalidVarInt) => true,
+            (Self::Overflow, Self::Overflow) => true,
+            (Self::LengthLimit { len: a, max: am }, Self::LengthLimit { len: b, max: bm }) => a == b && am == bm,
+            (Self::BufferTooSmall { needed: a, available: aa }, Self::BufferTooSmall { needed: b, available: ba }) => a == b && aa == ba,
+            (Self::RecursionLimit { max: a }, Self::RecursionLimit { max: b }) => a == b,
+            #[cfg(feature = "std")]
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind(),
+            #[cfg(feature = "alloc")]
+            (Self::Custom(a), Self::Custom(b)) => alloc::string::ToString::to_string(a) == alloc::string::ToString::to_string(b),
+            _ => false,
+        }
+    }
 }
 impl Display for PackError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             Self::SizeError => write!(f, "buffer is too small"),
+            Self::UnknownTag(tag) => write!(f, "unknown enum tag {}", tag),
+            Self::InvalidValue(msg) => write!(f, "invalid value: {}", msg),
+            Self::InvalidVarInt => write!(f, "overlong or overflowing variable-length integer"),
+            Self::LengthLimit { len, max } => write!(f, "length {} exceeds the limit of {}", len, max),
+            Self::BufferTooSmall { needed, available } => write!(f, "packing needs {} bytes but the buffer only has {}", needed, available),
+            Self::Overflow => write!(f, "integer does not fit in its packed width"),
+            Self::RecursionLimit { max } => write!(f, "values nested more than {} deep", max),
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(f, "i/o error: {}", e),
+            #[cfg(feature = "alloc")]
+            Self::Custom(e) => e.fmt(f),
         }
     }
 }
-impl std::error::Error for PackError {}
-impl From<io::Error> for PackError {
-    fn from(e: io::Error) -> Self {
+impl core::error::Error for PackError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Self::Custom(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<std_io::Error> for PackError {
+    fn from(e: std_io::Error) -> Self {
         match e.kind() {
-            io::ErrorKind::UnexpectedEof => Self::SizeError,
-            _ => unreachable!(),
+            std_io::ErrorKind::UnexpectedEof => Self::SizeError,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<PackError> for std_io::Error {
+    fn from(e: PackError) -> Self {
+        match e {
+            PackError::Io(e) => e,
+            PackError::SizeError => std_io::Error::new(std_io::ErrorKind::UnexpectedEof, e),
+            PackError::BufferTooSmall { .. } => std_io::Error::new(std_io::ErrorKind::WriteZero, e),
+            e => std_io::Error::new(std_io::ErrorKind::InvalidData, e),
         }
     }
 }
 
 impl Packable for bool {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u8(if *self {1} else {0})?;
@@ -61,7 +226,7 @@ impl Unpackable for bool {
 }
 
 impl Packable for u8 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u8(*self)?;
@@ -72,12 +237,12 @@ impl Unpackable for u8 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_u8()?)
+        rdr.read_u8()
     }
 }
 
 impl Packable for i8 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_i8(*self)?;
@@ -88,12 +253,12 @@ impl Unpackable for i8 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_i8()?)
+        rdr.read_i8()
     }
 }
 
 impl Packable for u16 {
-    type Error = io::Error;
+    type Error = PackError;
     
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u16::<BigEndian>(*self)?;
@@ -104,12 +269,12 @@ impl Unpackable for u16 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_u16::<BigEndian>()?)
+        rdr.read_u16::<BigEndian>()
     }
 }
 
 impl Packable for i16 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_i16::<BigEndian>(*self)?;
@@ -120,12 +285,12 @@ impl Unpackable for i16 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_i16::<BigEndian>()?)
+        rdr.read_i16::<BigEndian>()
     }
 }
 
 impl Packable for u32 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u32::<BigEndian>(*self)?;
@@ -136,12 +301,12 @@ impl Unpackable for u32 {
     type Error = PackError;
     
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_u32::<BigEndian>()?)
+        rdr.read_u32::<BigEndian>()
     }
 }
 
 impl Packable for i32 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_i32::<BigEndian>(*self)?;
@@ -152,12 +317,12 @@ impl Unpackable for i32 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_i32::<BigEndian>()?)
+        rdr.read_i32::<BigEndian>()
     }
 }
 
 impl Packable for u64 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u64::<BigEndian>(*self)?;
@@ -168,12 +333,12 @@ impl Unpackable for u64 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_u64::<BigEndian>()?)
+        rdr.read_u64::<BigEndian>()
     }
 }
 
 impl Packable for i64 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_i64::<BigEndian>(*self)?;
@@ -184,12 +349,12 @@ impl Unpackable for i64 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_i64::<BigEndian>()?)
+        rdr.read_i64::<BigEndian>()
     }
 }
 
 impl Packable for u128 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_u128::<BigEndian>(*self)?;
@@ -200,12 +365,12 @@ impl Unpackable for u128 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_u128::<BigEndian>()?)
+        rdr.read_u128::<BigEndian>()
     }
 }
 
 impl Packable for i128 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_i128::<BigEndian>(*self)?;
@@ -216,12 +381,12 @@ impl Unpackable for i128 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_i128::<BigEndian>()?)
+        rdr.read_i128::<BigEndian>()
     }
 }
 
 impl Packable for f32 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_f32::<BigEndian>(*self)?;
@@ -232,12 +397,12 @@ impl Unpackable for f32 {
     type Error = PackError;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_f32::<BigEndian>()?)
+        rdr.read_f32::<BigEndian>()
     }
 }
 
 impl Packable for f64 {
-    type Error = io::Error;
+    type Error = PackError;
 
     fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
         stream.write_f64::<BigEndian>(*self)?;
@@ -248,7 +413,7 @@ impl Unpackable for f64 {
     type Error = PackError;
     
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        Ok(rdr.read_f64::<BigEndian>()?)
+        rdr.read_f64::<BigEndian>()
     }
 }
 
@@ -267,16 +432,26 @@ impl<T: Unpackable, const S: usize> Unpackable for [T; S] {
     type Error = T::Error;
 
     fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
-        let mut v: Vec<T> = Vec::with_capacity(S);
+        try_array(|| T::unpack_from(rdr))
+    }
+}
 
-        for _ in 0..S {
-            v.push(unpack_from(rdr)?);
-        }
+// Builds an array from successive calls to `f`, stopping at the first error. Avoids both `unsafe`
+// and allocating, so arrays work without `alloc`.
+pub(crate) fn try_array<T, E, const S: usize>(mut f: impl FnMut() -> Result<T, E>) -> Result<[T; S], E> {
+    let mut err = None;
+    let v = [(); S].map(|_| match err {
+        Some(_) => None,
+        None => f().map_err(|e| err = Some(e)).ok(),
+    });
 
-        Ok(v.try_into().unwrap_or_else(|_| unreachable!()))
+    match err {
+        Some(e) => Err(e),
+        None => Ok(v.map(|v| v.unwrap_or_else(|| unreachable!()))),
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T: Packable> Packable for Vec<T> {
     type Error = T::Error;
 
@@ -289,6 +464,66 @@ impl<T: Packable> Packable for Vec<T> {
     }
 }
 
+pub(crate) const INVALID_OPTION_FLAG: PackError = PackError::InvalidValue("option presence flag is not 0 or 1");
+
+// Packed as a presence byte (0 or 1) followed by the value if present
+impl<T: Packable> Packable for Option<T> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        match self {
+            Some(v) => {
+                stream.write_u8(1)?;
+                v.pack_into(stream)
+            }
+            None => Ok(stream.write_u8(0)?),
+        }
+    }
+}
+impl<T: Unpackable> Unpackable for Option<T> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        match rdr.read_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::unpack_from(rdr)?)),
+            _ => Err(INVALID_OPTION_FLAG.into()),
+        }
+    }
+}
+// Tuples pack their fields in order
+macro_rules! tuple_impls {
+    ($($idx:tt $t:ident),+) => {
+        impl<$($t: Packable),+> Packable for ($($t,)+) where $(PackError: From<$t::Error>),+ {
+            type Error = PackError;
+
+            fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+                $(self.$idx.pack_into(stream)?;)+
+                Ok(())
+            }
+        }
+        impl<$($t: Unpackable),+> Unpackable for ($($t,)+) where $(PackError: From<$t::Error>),+ {
+            type Error = PackError;
+
+            fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+                Ok(($($t::unpack_from(rdr)?,)+))
+            }
+        }
+    };
+}
+tuple_impls!(0 A);
+tuple_impls!(0 A, 1 B);
+tuple_impls!(0 A, 1 B, 2 C);
+tuple_impls!(0 A, 1 B, 2 C, 3 D);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K);
+tuple_impls!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L);
+
 impl<T: Packable> Packable for &T {
     type Error = T::Error;
 
@@ -300,6 +535,7 @@ impl<T: Packable> Packable for &T {
 pub fn unpack_from<T: Unpackable>(rdr: &mut impl Read) -> Result<T, T::Error> {
     T::unpack_from(rdr)
 }
+#[allow(clippy::needless_lifetimes)]
 pub fn unpack<'a, T: Unpackable>(buf: &'a [u8]) -> Result<(T, &'a [u8]), T::Error> {
     T::unpack(buf)
 }
@@ -307,11 +543,15 @@ pub fn unpack<'a, T: Unpackable>(buf: &'a [u8]) -> Result<(T, &'a [u8]), T::Erro
 pub fn pack_into<T: Packable>(wtr: &mut impl Write, value: &T) -> Result<(), T::Error> {
     T::pack_into(value, wtr)
 }
+#[cfg(feature = "alloc")]
 pub fn pack<T: Packable>(value: &T) -> Result<Vec<u8>, T::Error> {
     T::pack(value)
 }
+pub fn pack_to_slice<T: Packable>(value: &T, buf: &mut [u8]) -> Result<usize, T::Error> {
+    T::pack_to_slice(value, buf)
+}
 
-pub trait WritePackExt: io::Write + Sized {
+pub trait WritePackExt: Write + Sized {
     fn pack<T: Packable>(&mut self, pack: &T) -> Result<(), T::Error>;
 }
 impl<T: Write> WritePackExt for T {
@@ -321,7 +561,7 @@ impl<T: Write> WritePackExt for T {
     }
 }
 
-pub trait ReadPackExt: io::Read {
+pub trait ReadPackExt: Read {
     fn unpack<T: Unpackable>(&mut self) -> Result<T, T::Error>;
 }
 impl<T: Read> ReadPackExt for T {
@@ -330,9 +570,139 @@ impl<T: Read> ReadPackExt for T {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use crate::*;
+    #[cfg(feature = "std")]
+    use std::io;
+
+    #[cfg(feature = "std")]
+    struct FailingReader(io::ErrorKind);
+    #[cfg(feature = "std")]
+    impl io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(self.0, "simulated"))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pack_error_io() {
+        let v1: Result<u32, PackError> = unpack_from(&mut crate::io::StdIo(FailingReader(io::ErrorKind::ConnectionReset)));
+        let v2: Result<u32, PackError> = unpack_from(&mut crate::io::StdIo(FailingReader(io::ErrorKind::UnexpectedEof)));
+
+        let e1 = v1.unwrap_err();
+        assert_eq!(e1, PackError::Io(io::ErrorKind::ConnectionReset.into()));
+        assert!(std::error::Error::source(&e1).is_some());
+        assert_eq!(v2, Err(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_pack_error_custom() {
+        let e = PackError::custom("bad checksum");
+
+        assert_eq!(e.to_string(), "bad checksum");
+        assert_eq!(e, PackError::custom("bad checksum"));
+        assert_ne!(e, PackError::InvalidValue("bad checksum"));
+        #[cfg(feature = "std")]
+        assert!(std::error::Error::source(&e).is_some());
+    }
+
+    // Packs with `io::Error` where it's available, to check errors convert both ways
+    #[cfg(feature = "std")]
+    type CheckedError = io::Error;
+    #[cfg(not(feature = "std"))]
+    type CheckedError = PackError;
+
+    struct Checked(u8);
+    impl Packable for Checked {
+        type Error = CheckedError;
+
+        fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+            self.0.pack_into(stream)?;
+            Ok(())
+        }
+    }
+    impl Unpackable for Checked {
+        type Error = PackError;
+
+        fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+            let v: u8 = unpack_from(rdr)?;
+            if v > 100 {
+                return Err(PackError::custom(format!("{} is out of range", v)));
+            }
+            Ok(Self(v))
+        }
+    }
+
+    #[test]
+    fn test_pack_error_conversion() {
+        let v1 = Checked(5).pack().unwrap();
+        let v2: Result<(Checked, _), PackError> = unpack(&[101]);
+        let v3: Result<(Checked, _), PackError> = unpack(&[]);
+
+        assert_eq!(v1, vec![5]);
+        assert_eq!(v2.err(), Some(PackError::custom("101 is out of range")));
+        assert_eq!(v3.err(), Some(PackError::SizeError));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pack_error_io_conversion() {
+        let e1: io::Error = PackError::SizeError.into();
+        let e2: io::Error = PackError::InvalidValue("x").into();
+
+        assert_eq!(e1.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(e2.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(PackError::from(e2), PackError::Io(io::ErrorKind::InvalidData.into()));
+    }
+
+    #[test]
+    fn test_pack_to_slice() {
+        let mut buf = [0xaa; 8];
+
+        let v1 = [0x0102u16, 0x0304].pack_to_slice(&mut buf);
+        assert_eq!(v1, Ok(4));
+        assert_eq!(buf, [1, 2, 3, 4, 0xaa, 0xaa, 0xaa, 0xaa]);
+
+        let v2 = pack_to_slice(&LenPrefixed::<u8, &str>::new("hello world"), &mut buf);
+        let v3 = 0u64.pack_to_slice(&mut buf[..7]);
+        let v4 = Checked(5).pack_to_slice(&mut []);
+
+        assert_eq!(v2, Err(PackError::BufferTooSmall { needed: 12, available: 8 }));
+        assert_eq!(v3, Err(PackError::BufferTooSmall { needed: 8, available: 7 }));
+        #[cfg(feature = "std")]
+        assert_eq!(v4.map_err(|e| e.kind()), Err(io::ErrorKind::WriteZero));
+        #[cfg(not(feature = "std"))]
+        assert_eq!(v4, Err(PackError::BufferTooSmall { needed: 1, available: 0 }));
+        assert_eq!(<[u8; 0]>::default().pack_to_slice(&mut []), Ok(0));
+    }
+
+    #[test]
+    fn test_roundtrip_option() {
+        let v1 = Some(0x1234u16).pack().unwrap();
+        let v2 = None::<u16>.pack().unwrap();
+        let v3 = [Some(true), None].pack().unwrap();
+
+        assert_eq!(v1, vec![1, 0x12, 0x34]);
+        assert_eq!(v2, vec![0]);
+        assert_eq!(v3, vec![1, 1, 0]);
+        assert_eq!(unpack(&v1), Ok((Some(0x1234u16), &[][..])));
+        assert_eq!(unpack(&v2), Ok((None::<u16>, &[][..])));
+        assert_eq!(unpack(&v3), Ok(([Some(true), None], &[][..])));
+    }
+
+    #[test]
+    fn test_unpack_option_errors() {
+        let v1: Result<(Option<u8>, _), PackError> = unpack(&[2, 0]);
+        let v2: Result<(Option<u8>, _), PackError> = unpack(&[1]);
+        let v3: Result<(Option<Checked>, _), PackError> = unpack(&[1, 101]);
+
+        assert_eq!(v1, Err(PackError::InvalidValue("option presence flag is not 0 or 1")));
+        assert_eq!(v2, Err(PackError::SizeError));
+        assert_eq!(v3.err(), Some(PackError::custom("101 is out of range")));
+    }
+
     #[test]
     fn test_pack_bool() {
         let t = true;
@@ -345,6 +715,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_unpack_bool() {
         let tb1 = [];
         let tb2 = [0x1];
@@ -1117,6 +1488,58 @@ mod tests {
         assert_eq!(b2, &[]);
     }
 
+    #[test]
+    fn test_pack_tuple() {
+        let t1 = (0x0102u16,);
+        let t2 = (0x01u8, 0x0203u16, [0x04u8, 0x05]);
+        let t3 = (1u8, 2u8, 3u8, 4u8, 5u8, 6u8, 7u8, 8u8, 9u8, 10u8, 11u8, 12u8);
+
+        assert_eq!(t1.pack().unwrap(), vec![0x01, 0x02]);
+        assert_eq!(t2.pack().unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+        assert_eq!(t3.pack().unwrap(), vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_unpack_tuple() {
+        let i1 = [0x01, 0x02, 0x03];
+        let i2 = [0x01, 0x02, 0x03, 0x04, 0xff];
+        let i3 = [0x01, 0x65];
+
+        let v1: Result<((u16, u16), _), PackError> = unpack(&i1);
+        let (v2, b2): ((u8, [u8; 2], u8), _) = unpack(&i2).unwrap();
+        let v3: Result<((u8, Checked), _), PackError> = unpack(&i3);
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, (0x01, [0x02, 0x03], 0x04));
+        assert_eq!(b2, &[0xff]);
+        assert_eq!(v3.err(), Some(PackError::custom("101 is out of range")));
+    }
+
+    type Mixed = (bool, f32, Option<u8>, LenPrefixed<u8, String>, VarU64, Le<i16>);
+    type Primitives = (u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
+
+    #[test]
+    fn test_roundtrip_tuple() {
+        let v1 = (0x1221u16, -5i32, [1u8, 2, 3, 4]);
+        let v2 = (true, 1.5f32, Some(7u8), LenPrefixed::<u8, String>::new(String::from("hi")), VarU64(300), Le(-2i16));
+        let v3 = (1u8, 2i8, 3u16, 4i16, 5u32, 6i32, 7u64, 8i64, 9u128, 10i128, 11.0f32, 12.0f64);
+
+        let i1 = v1.pack().unwrap();
+        let i2 = v2.pack().unwrap();
+        let i3 = v3.pack().unwrap();
+
+        let (t1, t1b): ((u16, i32, [u8; 4]), _) = unpack(&i1).unwrap();
+        let (t2, t2b): (Mixed, _) = unpack(&i2).unwrap();
+        let (t3, t3b): (Primitives, _) = unpack(&i3).unwrap();
+
+        assert_eq!(t1, v1);
+        assert_eq!(t1b, &[]);
+        assert_eq!(t2, v2);
+        assert_eq!(t2b, &[]);
+        assert_eq!(t3, v3);
+        assert_eq!(t3b, &[]);
+    }
+
     #[test]
     fn test_pack_array() {
         let a1: [u8; 0] = [];
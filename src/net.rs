@@ -0,0 +1,249 @@
+use core::fmt::{self, Display, Formatter};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use crate::io::{Read, Write};
+use crate::{PackError, Packable, PackedSize, Unpackable};
+
+// Tags for the `IpAddr` and `SocketAddr` variants, matching the IP version
+const TAG_V4: u8 = 4;
+const TAG_V6: u8 = 6;
+
+impl Packable for Ipv4Addr {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.octets().pack_into(stream)
+    }
+}
+impl Unpackable for Ipv4Addr {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(<[u8; 4]>::unpack_from(rdr)?.into())
+    }
+}
+
+impl Packable for Ipv6Addr {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.octets().pack_into(stream)
+    }
+}
+impl Unpackable for Ipv6Addr {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(<[u8; 16]>::unpack_from(rdr)?.into())
+    }
+}
+
+// Packed as the address followed by the port
+impl Packable for SocketAddrV4 {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.ip().pack_into(stream)?;
+        self.port().pack_into(stream)
+    }
+}
+impl Unpackable for SocketAddrV4 {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self::new(Unpackable::unpack_from(rdr)?, Unpackable::unpack_from(rdr)?))
+    }
+}
+
+// Packed as the address, port, flow info and scope id
+impl Packable for SocketAddrV6 {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.ip().pack_into(stream)?;
+        self.port().pack_into(stream)?;
+        self.flowinfo().pack_into(stream)?;
+        self.scope_id().pack_into(stream)
+    }
+}
+impl Unpackable for SocketAddrV6 {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            Unpackable::unpack_from(rdr)?,
+            Unpackable::unpack_from(rdr)?,
+            Unpackable::unpack_from(rdr)?,
+            Unpackable::unpack_from(rdr)?,
+        ))
+    }
+}
+
+// Packed as a tag byte of 4 or 6 followed by the address
+impl Packable for IpAddr {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        match self {
+            IpAddr::V4(ip) => (TAG_V4, ip).pack_into(stream),
+            IpAddr::V6(ip) => (TAG_V6, ip).pack_into(stream),
+        }
+    }
+}
+impl Unpackable for IpAddr {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        match u8::unpack_from(rdr)? {
+            TAG_V4 => Ok(IpAddr::V4(Unpackable::unpack_from(rdr)?)),
+            TAG_V6 => Ok(IpAddr::V6(Unpackable::unpack_from(rdr)?)),
+            tag => Err(PackError::UnknownTag(tag.into())),
+        }
+    }
+}
+
+// Packed as a tag byte of 4 or 6 followed by the socket address
+impl Packable for SocketAddr {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        match self {
+            SocketAddr::V4(addr) => (TAG_V4, addr).pack_into(stream),
+            SocketAddr::V6(addr) => (TAG_V6, addr).pack_into(stream),
+        }
+    }
+}
+impl Unpackable for SocketAddr {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        match u8::unpack_from(rdr)? {
+            TAG_V4 => Ok(SocketAddr::V4(Unpackable::unpack_from(rdr)?)),
+            TAG_V6 => Ok(SocketAddr::V6(Unpackable::unpack_from(rdr)?)),
+            tag => Err(PackError::UnknownTag(tag.into())),
+        }
+    }
+}
+
+/// 48-bit Ethernet hardware address, packed as its six octets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MacAddr(pub [u8; 6]);
+impl MacAddr {
+    pub const BROADCAST: Self = Self([0xff; 6]);
+
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+}
+impl Display for MacAddr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", a, b, c, d, e, g)
+    }
+}
+
+impl Packable for MacAddr {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into(stream)
+    }
+}
+impl Unpackable for MacAddr {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(Unpackable::unpack_from(rdr)?))
+    }
+}
+
+macro_rules! net_size {
+    ($($t:ty => $n:expr),*) => {$(
+        impl PackedSize for $t {
+            const SIZE: Option<usize> = Some($n);
+
+            fn packed_len(&self) -> usize {
+                $n
+            }
+        }
+    )*};
+}
+net_size!(Ipv4Addr => 4, Ipv6Addr => 16, SocketAddrV4 => 6, SocketAddrV6 => 26, MacAddr => 6);
+
+impl PackedSize for IpAddr {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        match self {
+            IpAddr::V4(ip) => 1 + ip.packed_len(),
+            IpAddr::V6(ip) => 1 + ip.packed_len(),
+        }
+    }
+}
+impl PackedSize for SocketAddr {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        match self {
+            SocketAddr::V4(addr) => 1 + addr.packed_len(),
+            SocketAddr::V6(addr) => 1 + addr.packed_len(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+    use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    #[test]
+    fn test_pack_addrs() {
+        let v1 = Ipv4Addr::new(192, 168, 1, 2);
+        let v2 = SocketAddrV4::new(v1, 0x1f90);
+        let v3 = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 443, 1, 2);
+        let v4 = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let v5 = MacAddr([0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+
+        let mut i3 = vec![0; 15];
+        i3.extend([1, 0x01, 0xbb, 0, 0, 0, 1, 0, 0, 0, 2].iter());
+
+        assert_eq!(v1.pack().unwrap(), vec![192, 168, 1, 2]);
+        assert_eq!(v2.pack().unwrap(), vec![192, 168, 1, 2, 0x1f, 0x90]);
+        assert_eq!(v3.pack().unwrap(), i3);
+        assert_eq!(v4.pack().unwrap(), vec![4, 127, 0, 0, 1]);
+        assert_eq!(v5.pack().unwrap(), vec![0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+        assert_eq!(v5.to_string(), "00:1a:2b:3c:4d:5e");
+    }
+
+    #[test]
+    fn test_roundtrip_addrs() {
+        let v1 = IpAddr::V6("2001:db8::1".parse().unwrap());
+        let v2: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let v3: SocketAddr = "[fe80::1%3]:53".parse().unwrap();
+        let v4 = MacAddr::BROADCAST;
+
+        let i = (v1, v2, v3, v4).pack().unwrap();
+        let (t, tb): ((IpAddr, SocketAddr, SocketAddr, MacAddr), _) = unpack(&i).unwrap();
+
+        assert_eq!(t, (v1, v2, v3, v4));
+        assert_eq!(tb, &[]);
+        assert_eq!(i.len(), (v1, v2, v3, v4).packed_len());
+        assert_eq!(SocketAddrV6::SIZE, Some(26));
+    }
+
+    #[test]
+    fn test_unpack_addrs_errors() {
+        let v1 = unpack::<IpAddr>(&[5, 1, 2, 3, 4]).err();
+        let v2 = unpack::<SocketAddr>(&[6, 1, 2, 3, 4]).err();
+        let v3 = unpack::<MacAddr>(&[1, 2, 3, 4, 5]).err();
+
+        assert_eq!(v1, Some(PackError::UnknownTag(5)));
+        assert_eq!(v2, Some(PackError::SizeError));
+        assert_eq!(v3, Some(PackError::SizeError));
+    }
+}
@@ -0,0 +1,219 @@
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8,
+    Wrapping,
+};
+use core::ops::Deref;
+
+use crate::io::{Read, Write};
+use crate::{PackError, Packable, PackedSize, Unpackable};
+
+macro_rules! width_wrapper {
+    ($($(#[$doc:meta])* $name:ident($t:ty);)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        pub struct $name<W> {
+            value: $t,
+            _width: PhantomData<W>,
+        }
+        impl<W> $name<W> {
+            pub fn new(value: $t) -> Self {
+                Self { value, _width: PhantomData }
+            }
+            pub fn get(self) -> $t {
+                self.value
+            }
+        }
+        impl<W> From<$t> for $name<W> {
+            fn from(value: $t) -> Self {
+                Self::new(value)
+            }
+        }
+        impl<W> Deref for $name<W> {
+            type Target = $t;
+
+            fn deref(&self) -> &$t {
+                &self.value
+            }
+        }
+
+        impl<W> Packable for $name<W>
+        where
+            W: Packable<Error = PackError> + TryFrom<$t>,
+        {
+            type Error = PackError;
+
+            fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+                W::try_from(self.value).map_err(|_| PackError::Overflow)?.pack_into(stream)
+            }
+        }
+        impl<W> Unpackable for $name<W>
+        where
+            W: Unpackable<Error = PackError>,
+            $t: TryFrom<W>,
+        {
+            type Error = PackError;
+
+            fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+                Ok(Self::new(<$t>::try_from(W::unpack_from(rdr)?).map_err(|_| PackError::Overflow)?))
+            }
+        }
+        impl<W: PackedSize + TryFrom<$t>> PackedSize for $name<W> {
+            const SIZE: Option<usize> = W::SIZE;
+
+            // Packing fails anyway if the value doesn't fit, so it gets the largest size
+            fn packed_len(&self) -> usize {
+                W::try_from(self.value).map_or(usize::MAX, |w| w.packed_len())
+            }
+        }
+    )*};
+}
+width_wrapper! {
+    /// Packs a `usize` as the integer type `W`, failing with `PackError::Overflow` if the value
+    /// doesn't fit on either end
+    Usize(usize);
+    /// Packs an `isize` as the integer type `W`, failing with `PackError::Overflow` if the value
+    /// doesn't fit on either end
+    Isize(isize);
+}
+
+// Packed as its scalar value in a u32
+impl Packable for char {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        u32::from(*self).pack_into(stream)
+    }
+}
+impl Unpackable for char {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        char::from_u32(u32::unpack_from(rdr)?).ok_or(PackError::InvalidValue("not a unicode scalar value"))
+    }
+}
+
+macro_rules! non_zero {
+    ($($t:ty => $inner:ty),*) => {$(
+        impl Packable for $t {
+            type Error = PackError;
+
+            fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+                self.get().pack_into(stream)
+            }
+        }
+        impl Unpackable for $t {
+            type Error = PackError;
+
+            fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+                <$t>::new(<$inner>::unpack_from(rdr)?).ok_or(PackError::InvalidValue("zero for a non-zero integer"))
+            }
+        }
+    )*};
+}
+non_zero!(
+    NonZeroU8 => u8, NonZeroU16 => u16, NonZeroU32 => u32, NonZeroU64 => u64, NonZeroU128 => u128,
+    NonZeroI8 => i8, NonZeroI16 => i16, NonZeroI32 => i32, NonZeroI64 => i64, NonZeroI128 => i128
+);
+
+macro_rules! fixed_size {
+    ($($t:ty),*) => {$(
+        impl PackedSize for $t {
+            const SIZE: Option<usize> = Some(size_of::<$t>());
+
+            fn packed_len(&self) -> usize {
+                size_of::<$t>()
+            }
+        }
+    )*};
+}
+fixed_size!(
+    char, NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128
+);
+
+impl<T: Packable> Packable for Wrapping<T> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into(stream)
+    }
+}
+impl<T: Unpackable> Unpackable for Wrapping<T> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Wrapping(T::unpack_from(rdr)?))
+    }
+}
+impl<T: PackedSize> PackedSize for Wrapping<T> {
+    const SIZE: Option<usize> = T::SIZE;
+
+    fn packed_len(&self) -> usize {
+        self.0.packed_len()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+    use core::num::{NonZeroI16, NonZeroU32, NonZeroU8, Wrapping};
+
+    #[test]
+    fn test_pack_width() {
+        assert_eq!(Usize::<u16>::new(0x1234).pack().unwrap(), vec![0x12, 0x34]);
+        assert_eq!(Usize::<u8>::new(256).pack(), Err(PackError::Overflow));
+        assert_eq!(Isize::<i8>::new(-1).pack().unwrap(), vec![0xff]);
+        assert_eq!(Isize::<i8>::new(-129).pack(), Err(PackError::Overflow));
+        assert_eq!(Isize::<u32>::new(-1).pack(), Err(PackError::Overflow));
+        assert_eq!(Usize::<u64>::SIZE, Some(8));
+        assert_eq!(Isize::<i16>::new(3).packed_len(), 2);
+        assert_eq!(Usize::<u8>::new(256).packed_len(), usize::MAX);
+    }
+
+    #[test]
+    fn test_roundtrip_width() {
+        let v1 = Usize::<u32>::new(70000);
+        let v2 = Isize::<i64>::new(-5);
+
+        let i = (v1, v2).pack().unwrap();
+        let (t, tb): ((Usize<u32>, Isize<i64>), _) = unpack(&i).unwrap();
+
+        assert_eq!(t, (v1, v2));
+        assert_eq!(tb, &[]);
+        assert_eq!(*t.0, 70000);
+        assert_eq!(t.1.get(), -5);
+        assert_eq!(unpack::<Usize<u128>>(&[0xff; 16]).err(), Some(PackError::Overflow));
+    }
+
+    #[test]
+    fn test_roundtrip_char() {
+        let v = ['a', 'é', '🦀'];
+
+        let i = v.pack().unwrap();
+        let (t, tb): ([char; 3], _) = unpack(&i).unwrap();
+
+        assert_eq!(&i[8..], &[0, 0x01, 0xf9, 0x80]);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+        assert_eq!(unpack::<char>(&[0, 0, 0xd8, 0]).err(), Some(PackError::InvalidValue("not a unicode scalar value")));
+        assert_eq!(unpack::<char>(&[0, 0x11, 0, 0]).err(), Some(PackError::InvalidValue("not a unicode scalar value")));
+    }
+
+    #[test]
+    fn test_roundtrip_non_zero() {
+        let v1 = NonZeroU32::new(7).unwrap();
+        let v2 = NonZeroI16::new(-2).unwrap();
+        let v3 = Wrapping(0xffu8);
+
+        let i = (v1, v2, v3).pack().unwrap();
+        let (t, tb): ((NonZeroU32, NonZeroI16, Wrapping<u8>), _) = unpack(&i).unwrap();
+
+        assert_eq!(i, vec![0, 0, 0, 7, 0xff, 0xfe, 0xff]);
+        assert_eq!(t, (v1, v2, v3));
+        assert_eq!(tb, &[]);
+        assert_eq!(unpack::<NonZeroU8>(&[0]).err(), Some(PackError::InvalidValue("zero for a non-zero integer")));
+        assert_eq!(unpack::<Wrapping<NonZeroU8>>(&[0]).err(), Some(PackError::InvalidValue("zero for a non-zero integer")));
+    }
+}
@@ -0,0 +1,186 @@
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::io::{Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::len::LengthPrefix;
+use crate::{EndianPackable, PackError, Packable, PackedSize, Unpackable};
+
+macro_rules! odd_int {
+    ($($(#[$doc:meta])* $name:ident($t:ty, $wide:ty, $bytes:expr, $write:ident, $read:ident);)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        pub struct $name($t);
+        impl $name {
+            pub const BITS: u32 = $bytes * 8;
+            pub const MIN: Self = Self(if <$t>::MIN == 0 { 0 } else { <$t>::MIN >> (<$t>::BITS - Self::BITS) });
+            pub const MAX: Self = Self(<$t>::MAX >> (<$t>::BITS - Self::BITS));
+
+            /// Returns `None` if `v` is out of range
+            pub const fn new(v: $t) -> Option<Self> {
+                if v < Self::MIN.0 || v > Self::MAX.0 {
+                    return None;
+                }
+                Some(Self(v))
+            }
+            pub const fn get(self) -> $t {
+                self.0
+            }
+        }
+        impl TryFrom<$t> for $name {
+            type Error = PackError;
+
+            fn try_from(v: $t) -> Result<Self, PackError> {
+                Self::new(v).ok_or(PackError::Overflow)
+            }
+        }
+        impl From<$name> for $t {
+            fn from(v: $name) -> $t {
+                v.0
+            }
+        }
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl EndianPackable for $name {
+            fn pack_into_endian<E: ByteOrder>(&self, stream: &mut impl Write) -> Result<(), PackError> {
+                stream.$write::<E>(<$wide>::from(self.0), $bytes)
+            }
+            fn unpack_from_endian<E: ByteOrder>(rdr: &mut impl Read) -> Result<Self, PackError> {
+                // Always in range, as only `$bytes` bytes are read
+                Ok(Self(rdr.$read::<E>($bytes)? as $t))
+            }
+        }
+        impl Packable for $name {
+            type Error = PackError;
+
+            fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+                self.pack_into_endian::<BigEndian>(stream)
+            }
+        }
+        impl Unpackable for $name {
+            type Error = PackError;
+
+            fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+                Self::unpack_from_endian::<BigEndian>(rdr)
+            }
+        }
+        impl PackedSize for $name {
+            const SIZE: Option<usize> = Some($bytes);
+
+            fn packed_len(&self) -> usize {
+                $bytes
+            }
+        }
+    )*};
+}
+odd_int! {
+    /// Unsigned 24-bit integer, packed in 3 bytes
+    U24(u32, u64, 3, write_uint, read_uint);
+    /// Signed 24-bit integer, packed in 3 bytes
+    I24(i32, i64, 3, write_int, read_int);
+    /// Unsigned 40-bit integer, packed in 5 bytes
+    U40(u64, u64, 5, write_uint, read_uint);
+    /// Signed 40-bit integer, packed in 5 bytes
+    I40(i64, i64, 5, write_int, read_int);
+    /// Unsigned 48-bit integer, packed in 6 bytes
+    U48(u64, u64, 6, write_uint, read_uint);
+    /// Signed 48-bit integer, packed in 6 bytes
+    I48(i64, i64, 6, write_int, read_int);
+    /// Unsigned 56-bit integer, packed in 7 bytes
+    U56(u64, u64, 7, write_uint, read_uint);
+    /// Signed 56-bit integer, packed in 7 bytes
+    I56(i64, i64, 7, write_int, read_int);
+}
+
+// The unsigned widths can also encode lengths, e.g. the 3-byte lengths common in TLS-like formats
+macro_rules! odd_length_prefix {
+    ($($t:ident),*) => {$(
+        impl LengthPrefix for $t {
+            fn from_len(len: usize) -> Result<Self, PackError> {
+                u64::try_from(len)
+                    .ok()
+                    .and_then(|v| <_>::try_from(v).ok())
+                    .and_then($t::new)
+                    .ok_or(PackError::LengthLimit { len: len as u64, max: u64::from($t::MAX.0) })
+            }
+            fn to_len(self) -> Result<usize, PackError> {
+                usize::try_from(self.0).map_err(|_| PackError::LengthLimit {
+                    len: u64::from(self.0),
+                    max: usize::MAX as u64,
+                })
+            }
+        }
+    )*};
+}
+odd_length_prefix!(U24, U40, U48, U56);
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_odd_int_range() {
+        assert_eq!(U24::MAX.get(), 0xff_ffff);
+        assert_eq!(U24::MIN.get(), 0);
+        assert_eq!(I24::MAX.get(), 0x7f_ffff);
+        assert_eq!(I24::MIN.get(), -0x80_0000);
+        assert_eq!(U56::MAX.get(), 0xff_ffff_ffff_ffff);
+        assert_eq!(I40::MIN.get(), -(1 << 39));
+        assert_eq!(U24::new(0x100_0000), None);
+        assert_eq!(I24::new(-0x80_0001), None);
+        assert_eq!(I48::try_from(1 << 47), Err(PackError::Overflow));
+        assert_eq!(U48::try_from(1 << 47).map(u64::from), Ok(1 << 47));
+    }
+
+    #[test]
+    fn test_pack_odd_int() {
+        let v1 = U24::new(0x01_0203).unwrap();
+        let v2 = I24::new(-2).unwrap();
+        let v3 = U48::new(0x0102_0304_0506).unwrap();
+
+        assert_eq!(v1.pack().unwrap(), vec![0x01, 0x02, 0x03]);
+        assert_eq!(Le(v1).pack().unwrap(), vec![0x03, 0x02, 0x01]);
+        assert_eq!(v2.pack().unwrap(), vec![0xff, 0xff, 0xfe]);
+        assert_eq!(Le(v2).pack().unwrap(), vec![0xfe, 0xff, 0xff]);
+        assert_eq!(v3.pack().unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(Be(v3).pack().unwrap(), vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+    }
+
+    type Odd = (U24, I24, Le<U40>, Le<I40>, U48, Le<I48>, U56, I56);
+
+    #[test]
+    fn test_roundtrip_odd_int() {
+        let v: Odd = (U24::MAX, I24::MIN, Le(U40::MAX), Le(I40::MIN), U48::MAX, Le(I48::new(-3).unwrap()), U56::MIN, I56::MAX);
+
+        let i = v.pack().unwrap();
+        let (t, tb): (Odd, _) = unpack(&i).unwrap();
+
+        assert_eq!(i.len(), 3 + 3 + 5 + 5 + 6 + 6 + 7 + 7);
+        assert_eq!(i.len(), v.packed_len());
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+        assert_eq!(unpack::<I24>(&[0x80, 0, 0]).map(|(v, _)| v.get()), Ok(-0x80_0000));
+        assert_eq!(unpack::<U24>(&[0, 0]).err(), Some(PackError::SizeError));
+    }
+
+    #[test]
+    fn test_odd_int_len_prefix() {
+        let v1: LenPrefixed<U24, Vec<u8>> = vec![0xaa; 2].into();
+        let v2: LenPrefixed<Le<U48>, &str> = LenPrefixed::new("ab");
+
+        let i1 = v1.pack().unwrap();
+        let (t1, b1): (LenPrefixed<U24, Vec<u8>>, _) = unpack(&i1).unwrap();
+
+        assert_eq!(i1, vec![0, 0, 2, 0xaa, 0xaa]);
+        assert_eq!(t1, v1);
+        assert_eq!(b1, &[]);
+        assert_eq!(v2.pack().unwrap(), vec![2, 0, 0, 0, 0, 0, b'a', b'b']);
+        assert_eq!(U24::from_len(1 << 24).err(), Some(PackError::LengthLimit { len: 1 << 24, max: 0xff_ffff }));
+    }
+}
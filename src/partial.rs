@@ -0,0 +1,160 @@
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::io::Read;
+use crate::{PackError, Unpackable};
+
+/// Outcome of unpacking from a buffer that may not hold the whole value yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status<T> {
+    Complete(T),
+    /// The input ended early. `needed` is a lower bound on the number of extra bytes required
+    /// before trying again, if known.
+    Incomplete { needed: Option<usize> },
+}
+impl<T> Status<T> {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+    pub fn complete(self) -> Option<T> {
+        match self {
+            Self::Complete(v) => Some(v),
+            Self::Incomplete { .. } => None,
+        }
+    }
+}
+
+// Slice reader that remembers how far short the input fell, if it ran out
+struct Tracking<'a> {
+    buf: &'a [u8],
+    short: Option<Option<usize>>,
+}
+impl Read for Tracking<'_> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        if buf.len() > self.buf.len() {
+            self.short.get_or_insert(Some(buf.len() - self.buf.len()));
+            return Err(PackError::SizeError);
+        }
+        let (head, rest) = self.buf.split_at(buf.len());
+        buf.copy_from_slice(head);
+        self.buf = rest;
+        Ok(())
+    }
+}
+
+/// Like `unpack`, but reports running out of input as `Status::Incomplete` instead of an error.
+/// Nothing is consumed unless the value is complete.
+pub fn try_unpack<T: Unpackable>(buf: &[u8]) -> Result<Status<(T, &[u8])>, T::Error> {
+    let mut rdr = Tracking { buf, short: None };
+    let res = T::unpack_from(&mut rdr);
+    match (res, rdr.short) {
+        (Ok(v), _) => Ok(Status::Complete((v, rdr.buf))),
+        (Err(_), Some(needed)) => Ok(Status::Incomplete { needed }),
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Accumulates received bytes and unpacks `T`s from them as they become complete. After an
+/// incomplete attempt, decoding isn't retried until at least the reported number of bytes arrive.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct StreamDecoder<T> {
+    buf: Vec<u8>,
+    wanted: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+#[cfg(feature = "alloc")]
+impl<T: Unpackable> StreamDecoder<T> {
+    pub fn new() -> Self {
+        Self { buf: Vec::new(), wanted: 0, _marker: PhantomData }
+    }
+    pub fn extend(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+    /// Bytes received but not yet decoded
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.wanted = 0;
+    }
+
+    /// Unpacks the next value, removing its bytes from the buffer. On error the buffer is left
+    /// untouched.
+    pub fn decode(&mut self) -> Result<Status<T>, T::Error> {
+        if self.buf.len() < self.wanted {
+            return Ok(Status::Incomplete { needed: Some(self.wanted - self.buf.len()) });
+        }
+
+        match try_unpack::<T>(&self.buf)? {
+            Status::Complete((v, rest)) => {
+                let used = self.buf.len() - rest.len();
+                self.buf.drain(..used);
+                self.wanted = 0;
+                Ok(Status::Complete(v))
+            }
+            Status::Incomplete { needed } => {
+                self.wanted = self.buf.len() + needed.unwrap_or(1);
+                Ok(Status::Incomplete { needed })
+            }
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: Unpackable> Default for StreamDecoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_try_unpack() {
+        let i = [0, 1, 0, 2, 0xff];
+
+        let v1: Status<([u16; 2], _)> = try_unpack(&i[..1]).unwrap();
+        let v2: Status<([u16; 2], _)> = try_unpack(&i[..3]).unwrap();
+        let v3: Status<([u16; 2], _)> = try_unpack(&i).unwrap();
+        let v4: Status<(LenPrefixed<u8, String>, _)> = try_unpack(&[5, b'a', b'b']).unwrap();
+        let v5: Status<(VarU64, _)> = try_unpack(&[0x80, 0x80]).unwrap();
+
+        assert_eq!(v1, Status::Incomplete { needed: Some(1) });
+        assert_eq!(v2, Status::Incomplete { needed: Some(1) });
+        assert_eq!(v3, Status::Complete(([1, 2], &[0xff][..])));
+        assert_eq!(v4, Status::Incomplete { needed: Some(3) });
+        assert_eq!(v5, Status::Incomplete { needed: Some(1) });
+    }
+
+    #[test]
+    fn test_try_unpack_errors() {
+        let v1: Result<Status<(LenPrefixed<u8, String>, _)>, PackError> = try_unpack(&[1, 0xff]);
+        let v2: Result<Status<(VarU64, _)>, PackError> = try_unpack(&[0x80, 0x00]);
+
+        assert_eq!(v1, Err(PackError::InvalidValue("string is not valid utf-8")));
+        assert_eq!(v2, Err(PackError::InvalidVarInt));
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let mut dec = StreamDecoder::<LenPrefixed<u16, Vec<u8>>>::new();
+
+        assert_eq!(dec.decode(), Ok(Status::Incomplete { needed: Some(2) }));
+        dec.extend(&[0, 4, 1]);
+        assert_eq!(dec.decode(), Ok(Status::Incomplete { needed: Some(1) }));
+        dec.extend(&[2]);
+        assert_eq!(dec.decode(), Ok(Status::Incomplete { needed: Some(1) }));
+        dec.extend(&[3, 4, 0]);
+        assert_eq!(dec.decode(), Ok(Status::Complete(vec![1, 2, 3, 4].into())));
+        assert_eq!(dec.buffered(), &[0]);
+        assert_eq!(dec.decode(), Ok(Status::Incomplete { needed: Some(1) }));
+        dec.extend(&[0]);
+        assert_eq!(dec.decode(), Ok(Status::Complete(vec![].into())));
+        assert_eq!(dec.buffered(), &[]);
+    }
+}
@@ -0,0 +1,248 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+
+use crate::io::{Nesting, Read, Write};
+use crate::{PackError, Packable, PackedSize, Unpackable};
+
+/// How many `Box`, `Rc` or `Arc` values may be nested while unpacking, unless overridden with
+/// `MaxDepth`. Bounds the stack used by recursive types read from untrusted input.
+pub const DEFAULT_RECURSION_LIMIT: usize = 128;
+
+// Reader for the contents of the outermost pointer, carrying the nesting depth down to the
+// pointers inside it. Reads through a trait object so recursive types instantiate it only once.
+struct Nested<'a> {
+    inner: &'a mut (dyn Read + 'a),
+    nesting: Nesting,
+}
+impl Read for Nested<'_> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PackError> {
+        self.inner.read_exact(buf)
+    }
+    fn nesting(&mut self) -> Option<&mut Nesting> {
+        Some(&mut self.nesting)
+    }
+}
+
+// Unpacks `T` one level deeper, failing if that exceeds the limit
+fn unpack_nested<T: Unpackable>(rdr: &mut impl Read) -> Result<T, T::Error> {
+    let nesting = match rdr.nesting() {
+        Some(nesting) => nesting,
+        None => {
+            let nesting = Nesting { depth: 0, max: DEFAULT_RECURSION_LIMIT };
+            return unpack_nested::<T>(&mut Nested { inner: rdr, nesting });
+        }
+    };
+    if nesting.depth >= nesting.max {
+        return Err(PackError::RecursionLimit { max: nesting.max }.into());
+    }
+    nesting.depth += 1;
+    let val = T::unpack_from(rdr);
+    if let Some(nesting) = rdr.nesting() {
+        nesting.depth -= 1;
+    }
+    val
+}
+
+/// Unpacks `T` allowing `N` more levels of `Box`, `Rc` or `Arc` nesting, instead of
+/// `DEFAULT_RECURSION_LIMIT` in total. Packs the same as `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MaxDepth<T, const N: usize>(pub T);
+
+impl<T: Packable, const N: usize> Packable for MaxDepth<T, N> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        self.0.pack_into(stream)
+    }
+}
+impl<T: Unpackable, const N: usize> Unpackable for MaxDepth<T, N> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let nesting = match rdr.nesting() {
+            Some(nesting) => nesting,
+            None => {
+                let nesting = Nesting { depth: 0, max: N };
+                return Ok(Self(T::unpack_from(&mut Nested { inner: rdr, nesting })?));
+            }
+        };
+        let outer = nesting.max;
+        nesting.max = nesting.depth.saturating_add(N);
+        let val = T::unpack_from(rdr);
+        if let Some(nesting) = rdr.nesting() {
+            nesting.max = outer;
+        }
+        Ok(Self(val?))
+    }
+}
+
+macro_rules! pointer_impls {
+    ($($p:ident),*) => {$(
+        impl<T: Packable> Packable for $p<T> {
+            type Error = T::Error;
+
+            fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+                (**self).pack_into(stream)
+            }
+        }
+        impl<T: Unpackable> Unpackable for $p<T> {
+            type Error = T::Error;
+
+            fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+                Ok($p::new(unpack_nested::<T>(rdr)?))
+            }
+        }
+    )*};
+}
+pointer_impls!(Box, Rc, Arc);
+
+impl<T: Packable + Clone> Packable for Cow<'_, T> {
+    type Error = T::Error;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        (**self).pack_into(stream)
+    }
+}
+// Always unpacks an owned value
+impl<T: Unpackable + Clone> Unpackable for Cow<'_, T> {
+    type Error = T::Error;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Cow::Owned(T::unpack_from(rdr)?))
+    }
+}
+
+macro_rules! pointer_size {
+    ($($p:ident),*) => {$(
+        impl<T: PackedSize> PackedSize for $p<T> {
+            const SIZE: Option<usize> = T::SIZE;
+
+            fn packed_len(&self) -> usize {
+                (**self).packed_len()
+            }
+        }
+    )*};
+}
+pointer_size!(Box, Rc, Arc);
+impl<T: PackedSize + Clone> PackedSize for Cow<'_, T> {
+    const SIZE: Option<usize> = T::SIZE;
+
+    fn packed_len(&self) -> usize {
+        (**self).packed_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use alloc::borrow::Cow;
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+
+    #[derive(Debug, PartialEq)]
+    enum Tree {
+        Leaf(u8),
+        Node(Box<Tree>, Box<Tree>),
+    }
+    impl Packable for Tree {
+        type Error = PackError;
+
+        fn pack_into(&self, stream: &mut impl io::Write) -> Result<(), Self::Error> {
+            match self {
+                Tree::Leaf(v) => (0u8, *v).pack_into(stream),
+                Tree::Node(l, r) => (1u8, l, r).pack_into(stream),
+            }
+        }
+    }
+    impl Unpackable for Tree {
+        type Error = PackError;
+
+        fn unpack_from(rdr: &mut impl io::Read) -> Result<Self, Self::Error> {
+            match u8::unpack_from(rdr)? {
+                0 => Ok(Tree::Leaf(unpack_from(rdr)?)),
+                1 => Ok(Tree::Node(unpack_from(rdr)?, unpack_from(rdr)?)),
+                tag => Err(PackError::UnknownTag(tag.into())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_pointers() {
+        let v1 = Box::new(0x1234u16);
+        let v2 = Rc::new([1u8, 2]);
+        let v3 = Arc::new(Some(-1i8));
+        let v4: Cow<u32> = Cow::Borrowed(&7);
+
+        let i1 = v1.pack().unwrap();
+        let i2 = v2.pack().unwrap();
+        let i3 = v3.pack().unwrap();
+        let i4 = v4.pack().unwrap();
+
+        let (t1, t1b): (Box<u16>, _) = unpack(&i1).unwrap();
+        let (t2, t2b): (Rc<[u8; 2]>, _) = unpack(&i2).unwrap();
+        let (t3, t3b): (Arc<Option<i8>>, _) = unpack(&i3).unwrap();
+        let (t4, t4b): (Cow<u32>, _) = unpack(&i4).unwrap();
+
+        assert_eq!(i1, vec![0x12, 0x34]);
+        assert_eq!(t1, v1);
+        assert_eq!(t1b, &[]);
+        assert_eq!(t2, v2);
+        assert_eq!(t2b, &[]);
+        assert_eq!(t3, v3);
+        assert_eq!(t3b, &[]);
+        assert!(matches!(t4, Cow::Owned(7)));
+        assert_eq!(t4b, &[]);
+    }
+
+    #[test]
+    fn test_roundtrip_recursive() {
+        let v = Tree::Node(Box::new(Tree::Leaf(1)), Box::new(Tree::Node(Box::new(Tree::Leaf(2)), Box::new(Tree::Leaf(3)))));
+
+        let i = v.pack().unwrap();
+        let (t, tb): (Tree, _) = unpack(&i).unwrap();
+
+        assert_eq!(i, vec![1, 0, 1, 1, 0, 2, 0, 3]);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        // 200 nested nodes along the left edge
+        let mut i = vec![1; 200];
+        i.extend([0, 0].iter().cycle().take(2 * 201));
+
+        let v1: Result<(Tree, _), PackError> = unpack(&i);
+        let v2: Result<(Tree, _), PackError> = unpack(&i[..3]);
+        let (v3, _): (Tree, _) = unpack(&i[199..]).unwrap();
+
+        assert_eq!(v1, Err(PackError::RecursionLimit { max: DEFAULT_RECURSION_LIMIT }));
+        // Failed unpacks release their depth
+        assert_eq!(v2, Err(PackError::SizeError));
+        assert_eq!(v3, Tree::Node(Box::new(Tree::Leaf(0)), Box::new(Tree::Leaf(0))));
+
+
+        let v4: Result<(MaxDepth<Tree, 150>, _), PackError> = unpack(&i);
+        let v5: Result<(MaxDepth<Tree, 150>, _), PackError> = unpack(&i[60..]);
+        let v6: Result<(MaxDepth<Tree, 1>, _), PackError> = unpack(&i[198..]);
+
+        assert_eq!(v4, Err(PackError::RecursionLimit { max: 150 }));
+        assert!(v5.is_ok());
+        assert_eq!(v6, Err(PackError::RecursionLimit { max: 1 }));
+    }
+
+    #[test]
+    fn test_max_depth_nested() {
+        // Limits apply from where `MaxDepth` is unpacked, inside the limit of any outer pointers
+        let i = [0, 1, 1, 0, 2, 0, 3, 0, 4];
+        let v1 = unpack::<Box<(u8, MaxDepth<Tree, 2>)>>(&i).map(|(v, _)| v.0);
+        let v2 = unpack::<Box<(u8, MaxDepth<Tree, 1>)>>(&i).err();
+        let v3 = unpack::<Box<Box<Box<u8>>>>(&i).map(|(v, _)| ***v);
+
+        assert_eq!(v1, Ok(0));
+        assert_eq!(v2, Some(PackError::RecursionLimit { max: 2 }));
+        assert_eq!(v3, Ok(0));
+    }
+}
@@ -0,0 +1,168 @@
+use core::mem::size_of;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Number of bytes a value takes up once packed
+pub trait PackedSize {
+    /// Packed size shared by every value of the type, or `None` if it depends on the value
+    const SIZE: Option<usize>;
+
+    /// Packed size of this value. Values that can't be packed at all, such as a length too large
+    /// for its prefix, report `usize::MAX`.
+    fn packed_len(&self) -> usize;
+}
+
+// `const fn` helpers for combining sizes, also used by the derive
+#[doc(hidden)]
+pub const fn size_add(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        _ => None,
+    }
+}
+#[doc(hidden)]
+pub const fn size_mul(a: Option<usize>, n: usize) -> Option<usize> {
+    match a {
+        // No values take no space, whatever their size
+        _ if n == 0 => Some(0),
+        Some(a) => Some(a * n),
+        None => None,
+    }
+}
+// Size shared by two alternatives, such as enum variants
+#[doc(hidden)]
+pub const fn size_same(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) if a == b => Some(a),
+        _ => None,
+    }
+}
+
+// Sums packed lengths, staying at `usize::MAX` once any of them can't be packed
+pub(crate) fn len_sum<T: PackedSize>(values: impl Iterator<Item = T>) -> usize {
+    values.fold(0, |len, v| len.saturating_add(v.packed_len()))
+}
+
+// Impls for the built-in types. The crate's own types implement `PackedSize` next to their
+// `Packable` impls.
+macro_rules! packed_size_fixed {
+    ($($t:ty),*) => {$(
+        impl PackedSize for $t {
+            const SIZE: Option<usize> = Some(size_of::<$t>());
+
+            fn packed_len(&self) -> usize {
+                size_of::<$t>()
+            }
+        }
+    )*};
+}
+packed_size_fixed!(bool, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
+
+impl<T: PackedSize, const S: usize> PackedSize for [T; S] {
+    const SIZE: Option<usize> = size_mul(T::SIZE, S);
+
+    fn packed_len(&self) -> usize {
+        match Self::SIZE {
+            Some(len) => len,
+            None => len_sum(self.iter()),
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: PackedSize> PackedSize for Vec<T> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        len_sum(self.iter())
+    }
+}
+impl<T: PackedSize> PackedSize for Option<T> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        self.as_ref().map_or(0, T::packed_len).saturating_add(1)
+    }
+}
+impl<T: PackedSize + ?Sized> PackedSize for &T {
+    const SIZE: Option<usize> = T::SIZE;
+
+    fn packed_len(&self) -> usize {
+        (**self).packed_len()
+    }
+}
+
+macro_rules! tuple_size {
+    ($($idx:tt $t:ident),+) => {
+        impl<$($t: PackedSize),+> PackedSize for ($($t,)+) {
+            const SIZE: Option<usize> = {
+                let size = Some(0);
+                $(let size = size_add(size, $t::SIZE);)+
+                size
+            };
+
+            fn packed_len(&self) -> usize {
+                0usize $(.saturating_add(self.$idx.packed_len()))+
+            }
+        }
+    };
+}
+tuple_size!(0 A);
+tuple_size!(0 A, 1 B);
+tuple_size!(0 A, 1 B, 2 C);
+tuple_size!(0 A, 1 B, 2 C, 3 D);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K);
+tuple_size!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L);
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_size_fixed() {
+        assert_eq!(u8::SIZE, Some(1));
+        assert_eq!(i128::SIZE, Some(16));
+        assert_eq!(<[[u16; 3]; 2]>::SIZE, Some(12));
+        assert_eq!(<Le<f64>>::SIZE, Some(8));
+        assert_eq!(<FixedWidth<String, 32>>::SIZE, Some(32));
+        assert_eq!(<[VarU64; 2]>::SIZE, None);
+        assert_eq!(<[VarU64; 0]>::SIZE, Some(0));
+        assert_eq!(<(u8, [VarU64; 0])>::SIZE, Some(1));
+        assert_eq!(<(u16, u32, [u8; 4])>::SIZE, Some(10));
+        assert_eq!(<(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u64)>::SIZE, Some(19));
+        assert_eq!(<(u8, VarU64)>::SIZE, None);
+        assert_eq!(<Box<[u16; 2]>>::SIZE, Some(4));
+    }
+
+    #[test]
+    fn test_packed_len() {
+        let v1: LenPrefixed<VarU64, Vec<VarU64>> = vec![VarU64(1), VarU64(300)].into();
+        let v2: LenPrefixed<u16, &str> = "hello".into();
+        let v3 = NulTerminated("hi");
+        let v4 = [VarI64(-1), VarI64(100)];
+
+        assert_eq!(v1.packed_len(), v1.pack().unwrap().len());
+        assert_eq!(v2.packed_len(), 7);
+        assert_eq!(v3.packed_len(), 3);
+        assert_eq!(v4.packed_len(), 3);
+        assert_eq!([1u32, 2].packed_len(), 8);
+        assert_eq!([Some(1u32), None].packed_len(), 6);
+        assert_eq!((1u8, VarU64(300), NulTerminated("a")).packed_len(), 5);
+        assert_eq!(alloc::rc::Rc::new(VarU64(300)).packed_len(), 2);
+    }
+
+    #[test]
+    fn test_packed_len_saturates() {
+        let v1: LenPrefixed<u8, Vec<u8>> = vec![0; 256].into();
+        let v2 = (1u8, Some(v1.clone()), [Usize::<u8>::new(256)]);
+
+        assert_eq!(v1.packed_len(), usize::MAX);
+        assert_eq!(v2.packed_len(), usize::MAX);
+        assert_eq!(vec![v1].packed_len(), usize::MAX);
+    }
+}
@@ -0,0 +1,324 @@
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+use crate::io::Write;
+#[cfg(feature = "alloc")]
+use crate::io::Read;
+#[cfg(feature = "alloc")]
+use crate::len::{unpack_len, MAX_PREALLOC};
+use crate::len::{len_size, pack_len, LengthPrefix};
+#[cfg(feature = "alloc")]
+use crate::Unpackable;
+use crate::{LenPrefixed, PackError, Packable, PackedSize};
+
+const INVALID_UTF8: PackError = PackError::InvalidValue("string is not valid utf-8");
+
+/// Byte view of the string and byte-string types that `NulTerminated` and `FixedWidth` pack
+pub trait AsBytes {
+    fn as_bytes(&self) -> &[u8];
+}
+#[cfg(feature = "alloc")]
+impl AsBytes for String {
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+}
+impl AsBytes for &str {
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+}
+#[cfg(feature = "alloc")]
+impl AsBytes for Vec<u8> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+impl AsBytes for &[u8] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Owned string or byte-string types that `NulTerminated` and `FixedWidth` unpack into, failing
+/// if the bytes aren't valid for the type
+#[cfg(feature = "alloc")]
+pub trait FromBytes: Sized {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, PackError>;
+}
+#[cfg(feature = "alloc")]
+impl FromBytes for String {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, PackError> {
+        String::from_utf8(bytes).map_err(|_| INVALID_UTF8)
+    }
+}
+#[cfg(feature = "alloc")]
+impl FromBytes for Vec<u8> {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, PackError> {
+        Ok(bytes)
+    }
+}
+
+pub(crate) fn str_from_utf8(bytes: &[u8]) -> Result<&str, PackError> {
+    core::str::from_utf8(bytes).map_err(|_| INVALID_UTF8)
+}
+
+// Reads `len` bytes, growing the buffer as data arrives so a bogus length can't force a huge
+// allocation up front
+#[cfg(feature = "alloc")]
+fn read_bytes(rdr: &mut impl Read, len: usize) -> Result<Vec<u8>, PackError> {
+    let mut buf = Vec::with_capacity(len.min(MAX_PREALLOC));
+    while buf.len() < len {
+        let start = buf.len();
+        buf.resize(start + (len - start).min(MAX_PREALLOC), 0);
+        rdr.read_exact(&mut buf[start..])?;
+    }
+    Ok(buf)
+}
+
+pub(crate) fn check_nul(bytes: &[u8]) -> Result<(), PackError> {
+    if bytes.contains(&0) {
+        return Err(PackError::InvalidValue("string contains a nul byte"));
+    }
+    Ok(())
+}
+
+impl<L: LengthPrefix> Packable for LenPrefixed<L, &str> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_len::<L>(self.len(), stream)?;
+        stream.write_all(self.as_bytes())?;
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix> Packable for LenPrefixed<L, String> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        LenPrefixed::<L, _>::new(self.as_str()).pack_into(stream)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix> Unpackable for LenPrefixed<L, String> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let len = unpack_len::<L>(rdr)?;
+        Ok(Self::new(String::from_bytes(read_bytes(rdr, len)?)?))
+    }
+}
+
+/// Packs a string or byte string followed by a nul byte. Values containing a nul or longer than
+/// `MAX_LEN` bytes can't be packed, and unpacking gives up once `MAX_LEN` bytes pass without a nul.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NulTerminated<C>(pub C);
+
+impl<C> NulTerminated<C> {
+    pub const MAX_LEN: usize = 64 * 1024;
+}
+
+fn check_nul_terminated_len(len: usize) -> Result<(), PackError> {
+    if len > NulTerminated::<()>::MAX_LEN {
+        return Err(PackError::LengthLimit { len: len as u64, max: NulTerminated::<()>::MAX_LEN as u64 });
+    }
+    Ok(())
+}
+
+impl<C: AsBytes> Packable for NulTerminated<C> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let bytes = self.0.as_bytes();
+        check_nul_terminated_len(bytes.len())?;
+        check_nul(bytes)?;
+        stream.write_all(bytes)?;
+        stream.write_all(&[0])?;
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<C: FromBytes> Unpackable for NulTerminated<C> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let mut bytes = Vec::new();
+        loop {
+            match u8::unpack_from(rdr)? {
+                0 => break,
+                b => bytes.push(b),
+            }
+            check_nul_terminated_len(bytes.len())?;
+        }
+        Ok(Self(C::from_bytes(bytes)?))
+    }
+}
+
+/// Packs a string or byte string into exactly `N` bytes, padded with trailing zeros. Unpacking
+/// stops at the first nul, so values containing a nul can't be packed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FixedWidth<C, const N: usize>(pub C);
+
+impl<C: AsBytes, const N: usize> Packable for FixedWidth<C, N> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let bytes = self.0.as_bytes();
+        if bytes.len() > N {
+            return Err(PackError::LengthLimit { len: bytes.len() as u64, max: N as u64 });
+        }
+        check_nul(bytes)?;
+        stream.write_all(bytes)?;
+        for _ in bytes.len()..N {
+            stream.write_all(&[0])?;
+        }
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<C: FromBytes, const N: usize> Unpackable for FixedWidth<C, N> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let mut bytes = read_bytes(rdr, N)?;
+        if let Some(end) = bytes.iter().position(|&b| b == 0) {
+            bytes.truncate(end);
+        }
+        Ok(Self(C::from_bytes(bytes)?))
+    }
+}
+
+impl<L: LengthPrefix + PackedSize> PackedSize for LenPrefixed<L, &str> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        len_size::<L>(self.len()).saturating_add(self.len())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<L: LengthPrefix + PackedSize> PackedSize for LenPrefixed<L, String> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        len_size::<L>(self.len()).saturating_add(self.len())
+    }
+}
+impl<C: AsBytes> PackedSize for NulTerminated<C> {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        self.0.as_bytes().len() + 1
+    }
+}
+impl<C: AsBytes, const N: usize> PackedSize for FixedWidth<C, N> {
+    const SIZE: Option<usize> = Some(N);
+
+    fn packed_len(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_pack_len_prefixed_str() {
+        let v1: LenPrefixed<u8, &str> = LenPrefixed::new("héllo");
+        let v2: LenPrefixed<u16, String> = String::new().into();
+        let v3: LenPrefixed<u8, String> = "x".repeat(256).into();
+
+        assert_eq!(v1.pack().unwrap(), b"\x06h\xc3\xa9llo");
+        assert_eq!(v2.pack().unwrap(), vec![0, 0]);
+        assert_eq!(v3.pack(), Err(PackError::LengthLimit { len: 256, max: 255 }));
+    }
+
+    #[test]
+    fn test_unpack_len_prefixed_str() {
+        let i1 = [3, b'a', b'b'];
+        let i2 = [2, 0xc3, 0x28];
+        let i3 = [2, b'a', b'b', 0xff];
+
+        let v1: Result<(LenPrefixed<u8, String>, _), PackError> = unpack(&i1);
+        let v2: Result<(LenPrefixed<u8, String>, _), PackError> = unpack(&i2);
+        let (v3, b3): (LenPrefixed<u8, String>, _) = unpack(&i3).unwrap();
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Err(PackError::InvalidValue("string is not valid utf-8")));
+        assert_eq!(v3.as_str(), "ab");
+        assert_eq!(b3, &[0xff]);
+    }
+
+    #[test]
+    fn test_roundtrip_nul_terminated() {
+        let v1 = NulTerminated(String::from("netpack"));
+        let v2 = NulTerminated(vec![1u8, 2, 3]);
+
+        let i1 = v1.pack().unwrap();
+        let i2 = v2.pack().unwrap();
+
+        let (t1, b1): (NulTerminated<String>, _) = unpack(&i1).unwrap();
+        let (t2, b2): (NulTerminated<Vec<u8>>, _) = unpack(&i2).unwrap();
+
+        assert_eq!(i1, b"netpack\0");
+        assert_eq!(t1, v1);
+        assert_eq!(b1, &[]);
+        assert_eq!(i2, vec![1, 2, 3, 0]);
+        assert_eq!(t2, v2);
+        assert_eq!(b2, &[]);
+    }
+
+    #[test]
+    fn test_nul_terminated_errors() {
+        let v1: Result<(NulTerminated<String>, _), PackError> = unpack(b"abc");
+        let v2: Result<(NulTerminated<String>, _), PackError> = unpack(&[0xff, 0]);
+
+        assert_eq!(NulTerminated("a\0b").pack(), Err(PackError::InvalidValue("string contains a nul byte")));
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, Err(PackError::InvalidValue("string is not valid utf-8")));
+    }
+
+    #[test]
+    fn test_nul_terminated_limit() {
+        let max = NulTerminated::<String>::MAX_LEN;
+        let long = vec![b'a'; max + 1];
+
+        let v1 = NulTerminated(&long[..max]).pack().unwrap();
+        let (t1, _): (NulTerminated<Vec<u8>>, _) = unpack(&v1).unwrap();
+        let v2: Result<(NulTerminated<Vec<u8>>, _), PackError> = unpack(&long);
+        let v3: Result<(NulTerminated<&[u8]>, _), PackError> = unpack_borrowed(&long);
+
+        assert_eq!(t1.0.len(), max);
+        assert_eq!(NulTerminated(&long[..]).pack(), Err(PackError::LengthLimit { len: max as u64 + 1, max: max as u64 }));
+        assert_eq!(v2, Err(PackError::LengthLimit { len: max as u64 + 1, max: max as u64 }));
+        assert_eq!(v3, Err(PackError::LengthLimit { len: max as u64 + 1, max: max as u64 }));
+    }
+
+    #[test]
+    fn test_roundtrip_fixed_width() {
+        let v1: FixedWidth<String, 6> = FixedWidth(String::from("abc"));
+        let v2: FixedWidth<&[u8], 2> = FixedWidth(&[1, 2]);
+
+        let i1 = v1.pack().unwrap();
+        let i2 = v2.pack().unwrap();
+
+        let (t1, b1): (FixedWidth<String, 6>, _) = unpack(&i1).unwrap();
+        let (t2, b2): (FixedWidth<Vec<u8>, 2>, _) = unpack(&[1, 2, 0xff]).unwrap();
+
+        assert_eq!(i1, b"abc\0\0\0");
+        assert_eq!(t1, v1);
+        assert_eq!(b1, &[]);
+        assert_eq!(i2, vec![1, 2]);
+        assert_eq!(t2.0, vec![1, 2]);
+        assert_eq!(b2, &[0xff]);
+    }
+
+    #[test]
+    fn test_fixed_width_errors() {
+        let v1: Result<(FixedWidth<String, 4>, _), PackError> = unpack(b"abc");
+
+        assert_eq!(FixedWidth::<_, 2>("abc").pack(), Err(PackError::LengthLimit { len: 3, max: 2 }));
+        assert_eq!(v1, Err(PackError::SizeError));
+    }
+}
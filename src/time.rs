@@ -0,0 +1,211 @@
+use core::convert::TryFrom;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::io::{Read, Write};
+use crate::{PackError, Packable, PackedSize, Unpackable};
+
+const OUT_OF_RANGE: PackError = PackError::InvalidValue("time is out of range");
+
+// Seconds from the NTP epoch (1900-01-01) to the Unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Values that can be packed as a time since an epoch: `Duration`s as-is, and (with `std`)
+/// `SystemTime`s relative to the Unix or NTP epoch.
+pub trait Timestamp: Sized {
+    /// Time since the epoch `epoch` seconds before the Unix epoch
+    fn to_duration(&self, epoch: u64) -> Result<Duration, PackError>;
+    fn from_duration(d: Duration, epoch: u64) -> Result<Self, PackError>;
+}
+impl Timestamp for Duration {
+    fn to_duration(&self, _: u64) -> Result<Duration, PackError> {
+        Ok(*self)
+    }
+    fn from_duration(d: Duration, _: u64) -> Result<Self, PackError> {
+        Ok(d)
+    }
+}
+#[cfg(feature = "std")]
+fn epoch_time(epoch: u64) -> Result<SystemTime, PackError> {
+    UNIX_EPOCH.checked_sub(Duration::from_secs(epoch)).ok_or(OUT_OF_RANGE)
+}
+#[cfg(feature = "std")]
+impl Timestamp for SystemTime {
+    fn to_duration(&self, epoch: u64) -> Result<Duration, PackError> {
+        self.duration_since(epoch_time(epoch)?).map_err(|_| OUT_OF_RANGE)
+    }
+    fn from_duration(d: Duration, epoch: u64) -> Result<Self, PackError> {
+        epoch_time(epoch)?.checked_add(d).ok_or(OUT_OF_RANGE)
+    }
+}
+
+/// Packs a time since the Unix epoch as whole seconds (`u64`) followed by nanoseconds (`u32`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SecsNanos<T>(pub T);
+
+impl<T: Timestamp> Packable for SecsNanos<T> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let d = self.0.to_duration(0)?;
+        d.as_secs().pack_into(stream)?;
+        d.subsec_nanos().pack_into(stream)
+    }
+}
+impl<T: Timestamp> Unpackable for SecsNanos<T> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let secs = u64::unpack_from(rdr)?;
+        let nanos = u32::unpack_from(rdr)?;
+        if nanos >= 1_000_000_000 {
+            return Err(PackError::InvalidValue("nanoseconds are out of range"));
+        }
+        Ok(Self(T::from_duration(Duration::new(secs, nanos), 0)?))
+    }
+}
+
+/// Packs a time since the Unix epoch as whole milliseconds in a `u64`, truncating anything finer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Millis<T>(pub T);
+
+impl<T: Timestamp> Packable for Millis<T> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let millis = self.0.to_duration(0)?.as_millis();
+        u64::try_from(millis).map_err(|_| OUT_OF_RANGE)?.pack_into(stream)
+    }
+}
+impl<T: Timestamp> Unpackable for Millis<T> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(T::from_duration(Duration::from_millis(u64::unpack_from(rdr)?), 0)?))
+    }
+}
+
+/// Packs a time as a 64-bit NTP timestamp: 32 bits of seconds and 32 bits of fraction. A
+/// `SystemTime` is relative to the NTP epoch (1900) and must fall in era 0, which ends in 2036.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ntp64<T>(pub T);
+
+impl<T: Timestamp> Packable for Ntp64<T> {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        let d = self.0.to_duration(NTP_UNIX_OFFSET)?;
+        let secs = u32::try_from(d.as_secs()).map_err(|_| OUT_OF_RANGE)?;
+        // Rounded up, so unpacking truncates back to the same nanosecond
+        let frac = (u64::from(d.subsec_nanos()) << 32).div_ceil(1_000_000_000);
+        ((u64::from(secs) << 32) | frac).pack_into(stream)
+    }
+}
+impl<T: Timestamp> Unpackable for Ntp64<T> {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let v = u64::unpack_from(rdr)?;
+        let nanos = ((v & 0xffff_ffff) * 1_000_000_000) >> 32;
+        Ok(Self(T::from_duration(Duration::new(v >> 32, nanos as u32), NTP_UNIX_OFFSET)?))
+    }
+}
+
+macro_rules! time_size {
+    ($($t:ident => $n:expr),*) => {$(
+        impl<T> PackedSize for $t<T> {
+            const SIZE: Option<usize> = Some($n);
+
+            fn packed_len(&self) -> usize {
+                $n
+            }
+        }
+    )*};
+}
+time_size!(SecsNanos => 12, Millis => 8, Ntp64 => 8);
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+    use core::time::Duration;
+    #[cfg(feature = "std")]
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_pack_time() {
+        let d = Duration::new(2, 500_000_000);
+
+        assert_eq!(SecsNanos(d).pack().unwrap(), vec![0, 0, 0, 0, 0, 0, 0, 2, 0x1d, 0xcd, 0x65, 0]);
+        assert_eq!(Millis(d).pack().unwrap(), vec![0, 0, 0, 0, 0, 0, 0x09, 0xc4]);
+        assert_eq!(Ntp64(d).pack().unwrap(), vec![0, 0, 0, 2, 0x80, 0, 0, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pack_system_time() {
+        let t = UNIX_EPOCH + Duration::from_millis(1_000_001);
+
+        assert_eq!(Millis(t).pack().unwrap(), vec![0, 0, 0, 0, 0, 0x0f, 0x42, 0x41]);
+        // 1970-01-01 is 2208988800 seconds into NTP era 0
+        assert_eq!(Ntp64(UNIX_EPOCH).pack().unwrap(), vec![0x83, 0xaa, 0x7e, 0x80, 0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "std")]
+    type Times = (SecsNanos<SystemTime>, Millis<Duration>, Ntp64<SystemTime>, Ntp64<Duration>);
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_roundtrip_time() {
+        let v1 = SecsNanos(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789));
+        let v2 = Millis(Duration::from_millis(u64::MAX));
+        let v3 = Ntp64(UNIX_EPOCH + Duration::new(1_700_000_000, 999_999_999));
+        let v4 = Ntp64(Duration::new(7, 1));
+
+        let i = (v1, v2, v3, v4).pack().unwrap();
+        let (t, tb): (Times, _) = unpack(&i).unwrap();
+
+        assert_eq!(t, (v1, v2, v3, v4));
+        assert_eq!(tb, &[]);
+        assert_eq!(i.len(), (v1, v2, v3, v4).packed_len());
+    }
+
+    #[test]
+    fn test_roundtrip_duration() {
+        let v = (SecsNanos(Duration::new(1_700_000_000, 123_456_789)), Millis(Duration::from_millis(u64::MAX)), Ntp64(Duration::new(7, 1)));
+
+        let mut buf = [0; 28];
+        let len = v.pack_to_slice(&mut buf).unwrap();
+        let (t, tb): ((SecsNanos<Duration>, Millis<Duration>, Ntp64<Duration>), _) = unpack(&buf[..len]).unwrap();
+
+        assert_eq!(len, 28);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+    }
+
+    #[test]
+    fn test_time_range_errors() {
+        assert_eq!(Millis(Duration::MAX).pack(), Err(PackError::InvalidValue("time is out of range")));
+        assert_eq!(Ntp64(Duration::from_secs(1 << 32)).pack(), Err(PackError::InvalidValue("time is out of range")));
+
+        let v1 = unpack::<SecsNanos<Duration>>(&[0, 0, 0, 0, 0, 0, 0, 0, 0x3b, 0x9a, 0xca, 0x00]).err();
+
+        assert_eq!(v1, Some(PackError::InvalidValue("nanoseconds are out of range")));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_system_time_range_errors() {
+        let before_epoch = UNIX_EPOCH - Duration::from_secs(1);
+        let after_era = UNIX_EPOCH + Duration::from_secs(1 << 32);
+
+        assert_eq!(SecsNanos(before_epoch).pack(), Err(PackError::InvalidValue("time is out of range")));
+        assert_eq!(Ntp64(after_era).pack(), Err(PackError::InvalidValue("time is out of range")));
+
+        let v2 = unpack::<SecsNanos<SystemTime>>(&[0xff; 12]).err();
+        let v3 = unpack::<SecsNanos<SystemTime>>(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]).err();
+
+        assert_eq!(v2, Some(PackError::InvalidValue("nanoseconds are out of range")));
+        assert_eq!(v3, Some(PackError::InvalidValue("time is out of range")));
+    }
+}
@@ -0,0 +1,281 @@
+use core::convert::TryFrom;
+
+use crate::io::{Read, Write};
+
+use crate::{LengthPrefix, PackError, Packable, PackedSize, Unpackable};
+
+fn pack_leb128(mut v: u64, stream: &mut impl Write) -> Result<(), PackError> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+
+    stream.write_all(&buf[..len])?;
+    Ok(())
+}
+fn unpack_leb128(rdr: &mut impl Read) -> Result<u64, PackError> {
+    let mut v = 0u64;
+
+    for i in 0..10 {
+        let byte = u8::unpack_from(rdr)?;
+        let bits = u64::from(byte & 0x7f);
+        // The tenth byte only has room for the top bit of a u64
+        if i == 9 && bits > 1 {
+            return Err(PackError::InvalidVarInt);
+        }
+        v |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            // A zero final byte means the previous byte could have ended the encoding
+            if i > 0 && byte == 0 {
+                return Err(PackError::InvalidVarInt);
+            }
+            return Ok(v);
+        }
+    }
+
+    Err(PackError::InvalidVarInt)
+}
+
+/// Unsigned LEB128: 7 bits per byte, least significant group first, high bit set on all but the
+/// last byte. Takes 1 to 10 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct VarU64(pub u64);
+
+impl Packable for VarU64 {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_leb128(self.0, stream)
+    }
+}
+impl Unpackable for VarU64 {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        Ok(Self(unpack_leb128(rdr)?))
+    }
+}
+impl LengthPrefix for VarU64 {
+    fn from_len(len: usize) -> Result<Self, PackError> {
+        Ok(Self(len as u64))
+    }
+    fn to_len(self) -> Result<usize, PackError> {
+        self.0.to_len()
+    }
+}
+
+/// Signed LEB128 after ZigZag mapping (0, -1, 1, -2, ... become 0, 1, 2, 3, ...), so small
+/// negative numbers stay short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct VarI64(pub i64);
+
+impl Packable for VarI64 {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        pack_leb128(((self.0 << 1) ^ (self.0 >> 63)) as u64, stream)
+    }
+}
+impl Unpackable for VarI64 {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let v = unpack_leb128(rdr)?;
+        Ok(Self((v >> 1) as i64 ^ -((v & 1) as i64)))
+    }
+}
+
+/// QUIC variable-length integer (RFC 9000 section 16): the top two bits of the first byte give
+/// the total length of 1, 2, 4 or 8 bytes, leaving 6 to 62 bits for the value. Values are packed
+/// in the shortest encoding, but longer ones are accepted as the RFC allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct QuicVarInt(pub u64);
+impl QuicVarInt {
+    pub const MAX: u64 = (1 << 62) - 1;
+}
+
+impl Packable for QuicVarInt {
+    type Error = PackError;
+
+    fn pack_into(&self, stream: &mut impl Write) -> Result<(), Self::Error> {
+        match self.0 {
+            v if v < 1 << 6 => (v as u8).pack_into(stream),
+            v if v < 1 << 14 => (v as u16 | 0x4000).pack_into(stream),
+            v if v < 1 << 30 => (v as u32 | 0x8000_0000).pack_into(stream),
+            v if v <= Self::MAX => (v | 0xc000_0000_0000_0000).pack_into(stream),
+            _ => Err(PackError::Overflow),
+        }
+    }
+}
+impl Unpackable for QuicVarInt {
+    type Error = PackError;
+
+    fn unpack_from(rdr: &mut impl Read) -> Result<Self, Self::Error> {
+        let first = u8::unpack_from(rdr)?;
+        let len = 1usize << (first >> 6);
+        let mut v = u64::from(first & 0x3f);
+
+        for _ in 1..len {
+            v = (v << 8) | u64::from(u8::unpack_from(rdr)?);
+        }
+
+        Ok(Self(v))
+    }
+}
+impl LengthPrefix for QuicVarInt {
+    fn from_len(len: usize) -> Result<Self, PackError> {
+        match u64::try_from(len) {
+            Ok(v) if v <= Self::MAX => Ok(Self(v)),
+            _ => Err(PackError::LengthLimit { len: len as u64, max: Self::MAX }),
+        }
+    }
+    fn to_len(self) -> Result<usize, PackError> {
+        self.0.to_len()
+    }
+}
+
+fn leb128_len(v: u64) -> usize {
+    let bits = 64 - v.leading_zeros() as usize;
+    bits.max(1).div_ceil(7)
+}
+impl PackedSize for VarU64 {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        leb128_len(self.0)
+    }
+}
+impl PackedSize for VarI64 {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        leb128_len(((self.0 << 1) ^ (self.0 >> 63)) as u64)
+    }
+}
+impl PackedSize for QuicVarInt {
+    const SIZE: Option<usize> = None;
+
+    fn packed_len(&self) -> usize {
+        match self.0 {
+            v if v < 1 << 6 => 1,
+            v if v < 1 << 14 => 2,
+            v if v < 1 << 30 => 4,
+            _ => 8,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_pack_var_u64() {
+        assert_eq!(VarU64(0).pack().unwrap(), vec![0x00]);
+        assert_eq!(VarU64(127).pack().unwrap(), vec![0x7f]);
+        assert_eq!(VarU64(128).pack().unwrap(), vec![0x80, 0x01]);
+        assert_eq!(VarU64(624485).pack().unwrap(), vec![0xe5, 0x8e, 0x26]);
+        assert_eq!(VarU64(u64::MAX).pack().unwrap(), vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+    }
+
+    #[test]
+    fn test_unpack_var_u64() {
+        let i1 = [0x80];
+        let i2 = [0xe5, 0x8e, 0x26, 0xff];
+        let i3 = [0x80, 0x00];
+        let i4 = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+        let i5 = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x81, 0x00];
+
+        let v1: Result<(VarU64, _), PackError> = unpack(&i1);
+        let (v2, b2): (VarU64, _) = unpack(&i2).unwrap();
+        let v3: Result<(VarU64, _), PackError> = unpack(&i3);
+        let v4: Result<(VarU64, _), PackError> = unpack(&i4);
+        let v5: Result<(VarU64, _), PackError> = unpack(&i5);
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, VarU64(624485));
+        assert_eq!(b2, &[0xff]);
+        assert_eq!(v3, Err(PackError::InvalidVarInt));
+        assert_eq!(v4, Err(PackError::InvalidVarInt));
+        assert_eq!(v5, Err(PackError::InvalidVarInt));
+    }
+
+    #[test]
+    fn test_roundtrip_var_i64() {
+        for &(v, len) in &[(0i64, 1), (-1, 1), (1, 1), (-64, 1), (64, 2), (i64::MIN, 10), (i64::MAX, 10)] {
+            let i = VarI64(v).pack().unwrap();
+            let (t, tb): (VarI64, _) = unpack(&i).unwrap();
+
+            assert_eq!(i.len(), len);
+            assert_eq!(t, VarI64(v));
+            assert_eq!(tb, &[]);
+        }
+        assert_eq!(VarI64(-1).pack().unwrap(), vec![0x01]);
+        assert_eq!(VarI64(1).pack().unwrap(), vec![0x02]);
+    }
+
+    #[test]
+    fn test_pack_quic_var_int() {
+        assert_eq!(QuicVarInt(37).pack().unwrap(), vec![0x25]);
+        assert_eq!(QuicVarInt(15293).pack().unwrap(), vec![0x7b, 0xbd]);
+        assert_eq!(QuicVarInt(494878333).pack().unwrap(), vec![0x9d, 0x7f, 0x3e, 0x7d]);
+        assert_eq!(
+            QuicVarInt(151288809941952652).pack().unwrap(),
+            vec![0xc2, 0x19, 0x7c, 0x5e, 0xff, 0x14, 0xe8, 0x8c]
+        );
+        assert_eq!(QuicVarInt(1 << 62).pack(), Err(PackError::Overflow));
+    }
+
+    #[test]
+    fn test_unpack_quic_var_int() {
+        let i1 = [0x7b];
+        let i2 = [0x9d, 0x7f, 0x3e, 0x7d, 0xff];
+        let i3 = [0x40, 0x25];
+        let i4 = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+
+        let v1: Result<(QuicVarInt, _), PackError> = unpack(&i1);
+        let (v2, b2): (QuicVarInt, _) = unpack(&i2).unwrap();
+        let (v3, _): (QuicVarInt, _) = unpack(&i3).unwrap();
+        let (v4, b4): (QuicVarInt, _) = unpack(&i4).unwrap();
+
+        assert_eq!(v1, Err(PackError::SizeError));
+        assert_eq!(v2, QuicVarInt(494878333));
+        assert_eq!(b2, &[0xff]);
+        assert_eq!(v3, QuicVarInt(37));
+        assert_eq!(v4, QuicVarInt(QuicVarInt::MAX));
+        assert_eq!(b4, &[]);
+    }
+
+    #[test]
+    fn test_packed_len_var_ints() {
+        let values = [0, 1, 127, 128, 16383, 16384, u64::MAX];
+        for &v in &values {
+            assert_eq!(VarU64(v).packed_len(), VarU64(v).pack().unwrap().len());
+            assert_eq!(VarI64(v as i64).packed_len(), VarI64(v as i64).pack().unwrap().len());
+            let q = QuicVarInt(v.min(QuicVarInt::MAX));
+            assert_eq!(q.packed_len(), q.pack().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_var_len_prefixed() {
+        let v: LenPrefixed<VarU64, Vec<u8>> = vec![7; 200].into();
+
+        let i = v.pack().unwrap();
+        let (t, tb): (LenPrefixed<VarU64, Vec<u8>>, _) = unpack(&i).unwrap();
+
+        assert_eq!(&i[..2], &[0xc8, 0x01]);
+        assert_eq!(t, v);
+        assert_eq!(tb, &[]);
+    }
+}